@@ -0,0 +1,125 @@
+//! Throughput of the SSE decoder on multi-megabyte streams
+//!
+//! Run with `cargo bench --features bench --bench sse_decoder`. Time per MB must stay
+//! flat as the stream grows (the run fails if it grows MAX_GROWTH times from 1 MB to
+//! 16 MB); the previous `buffer = buffer[pos + 2..].to_string()` loop is included for
+//! comparison and grows quadratically once large chunks arrive in a burst.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use app_lib::sse_decoder::{SseDecoder, SseItem};
+
+const MB: usize = 1024 * 1024;
+const ROUNDS: u32 = 5;
+/// Builds a stream of about the given size in bytes
+type Input = fn(usize) -> Vec<u8>;
+/// Largest accepted growth of time per MB between the 1 MB and 16 MB streams
+const MAX_GROWTH: f64 = 3.0;
+
+/// `chat:tool-result-delta` events with ~1 KB of JSON each (agent reading a large file)
+fn tool_result_burst(total: usize) -> Vec<u8> {
+    let line = format!(
+        "event: chat:tool-result-delta\ndata: {{\"toolUseId\":\"toolu_01\",\"delta\":\"{}\"}}\n\n",
+        "fn main() { println!(\\\"读取文件内容\\\"); } ".repeat(24)
+    );
+    line.repeat(total / line.len() + 1).into_bytes()
+}
+
+/// One huge data line (a whole file as a single event)
+fn single_large_event(total: usize) -> Vec<u8> {
+    format!("event: chat:tool-result-complete\ndata: {}\n\n", "x".repeat(total)).into_bytes()
+}
+
+fn decode(input: &[u8], chunk_size: usize) -> usize {
+    let mut decoder = SseDecoder::new();
+    let mut events = 0;
+    for chunk in input.chunks(chunk_size) {
+        events += decoder
+            .feed(chunk)
+            .into_iter()
+            .filter(|item| matches!(item, SseItem::Event(_)))
+            .count();
+    }
+    events
+}
+
+/// The pre-decoder loop from `connect_sse`, kept for comparison
+fn decode_naive(input: &[u8], chunk_size: usize) -> usize {
+    let mut buffer = String::new();
+    let mut events = 0;
+    for chunk in input.chunks(chunk_size) {
+        buffer.push_str(&String::from_utf8_lossy(chunk));
+        while let Some(pos) = buffer.find("\n\n") {
+            let event_str = buffer[..pos].to_string();
+            buffer = buffer[pos + 2..].to_string();
+            events += usize::from(black_box(event_str).contains("data:"));
+        }
+    }
+    events
+}
+
+fn measure(f: impl Fn() -> usize) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+/// Print time per MB for each size; returns how much it grew from 1 MB to 16 MB
+fn report(name: &str, sizes: &[usize], run: impl Fn(&[u8]) -> usize, input: impl Fn(usize) -> Vec<u8>) -> f64 {
+    println!("{}", name);
+    let mut per_mb = Vec::new();
+    for &size in sizes {
+        let data = input(size * MB);
+        let elapsed = measure(|| run(&data));
+        let ms_per_mb = elapsed.as_secs_f64() * 1000.0 / size as f64;
+        per_mb.push(ms_per_mb);
+        println!(
+            "  {:>3} MB: {:>9.2} ms  ({:>7.1} MB/s, {:.3} ms/MB)",
+            size,
+            elapsed.as_secs_f64() * 1000.0,
+            size as f64 / elapsed.as_secs_f64(),
+            ms_per_mb
+        );
+    }
+    if let (Some(first), Some(last)) = (per_mb.first(), per_mb.last()) {
+        println!("  time per MB grew {:.2}x from smallest to largest stream\n", last / first);
+    }
+    let at = |mb: usize| sizes.iter().position(|&size| size == mb).map(|i| per_mb[i]);
+    match (at(1), at(16)) {
+        (Some(small), Some(large)) => large / small,
+        _ => f64::NAN,
+    }
+}
+
+fn assert_linear(name: &str, growth: f64) {
+    assert!(
+        growth < MAX_GROWTH,
+        "{}: time per MB grew {:.2}x from 1 MB to 16 MB (limit {}x)",
+        name, growth, MAX_GROWTH
+    );
+}
+
+fn main() {
+    let sizes = [1, 4, 16, 64];
+    // 16 KB ≈ typical socket reads; 4 MB ≈ a burst delivered as one chunk
+    let runs: [(&str, usize, Input); 3] = [
+        ("decoder, tool-result burst, 16 KB chunks", 16 * 1024, tool_result_burst),
+        ("decoder, tool-result burst, 4 MB chunks", 4 * MB, tool_result_burst),
+        ("decoder, single large event, 16 KB chunks", 16 * 1024, single_large_event),
+    ];
+    let growth: Vec<(&str, f64)> = runs
+        .iter()
+        .map(|&(name, chunk_size, input)| (name, report(name, &sizes, |d| decode(d, chunk_size), input)))
+        .collect();
+    report("naive loop, tool-result burst, 4 MB chunks", &[1, 2, 4], |d| decode_naive(d, 4 * MB), tool_result_burst);
+
+    for (name, growth) in growth {
+        assert_linear(name, growth);
+    }
+}
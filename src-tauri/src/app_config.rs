@@ -0,0 +1,621 @@
+//! Typed application configuration loaded from `~/.myagents/config.json`
+//!
+//! The file is owned by the frontend (see `src/renderer/config/configService.ts`);
+//! Rust only reads the sections it needs:
+//! - `proxySettings` → see `proxy_config.rs`
+//! - `tlsSettings` → see `tls_config.rs`
+//! - `runtimeSettings` → sidecar and proxy timeouts, port range, restart budget
+//! - `httpProxyPolicy` → see `http_policy.rs`
+//!
+//! Every value has a default and a valid range. Invalid values are reported with
+//! a clear log message and replaced by their default, so a typo never prevents
+//! the app from starting.
+//!
+//! The parsed config is cached (`current()`) and kept fresh by a file watcher
+//! (`spawn_config_watcher`), which debounces edits, re-validates the file and
+//! publishes a `config:changed` event with the list of changed paths.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::http_policy::HttpProxyPolicySettings;
+use crate::proxy_config::ProxySettings;
+use crate::tls_config::TlsSettings;
+
+// Watcher: poll file metadata every 250ms, reload once it has been stable for 300ms
+const WATCH_POLL_INTERVAL_MS: u64 = 250;
+const WATCH_DEBOUNCE_MS: u64 = 300;
+
+/// Default: 60 attempts × 100ms = 6 seconds until a sidecar must be healthy
+pub const DEFAULT_HEALTH_CHECK_MAX_ATTEMPTS: u32 = 60;
+/// Default: wait 5s for a sidecar to exit after SIGTERM before killing it
+pub const DEFAULT_GRACEFUL_SHUTDOWN_TIMEOUT_SECS: u64 = 5;
+/// Default: first port tried for sidecars
+pub const DEFAULT_BASE_PORT: u16 = 31415;
+/// Default: 500 ports (31415-31914)
+pub const DEFAULT_PORT_RANGE: u16 = 500;
+/// Default: SSE idle timeout, 4x the backend's 15s heartbeat
+pub const DEFAULT_SSE_READ_TIMEOUT_SECS: u64 = 60;
+/// Default: total timeout for proxied HTTP requests (covers slow model calls)
+pub const DEFAULT_HTTP_PROXY_TIMEOUT_SECS: u64 = 120;
+/// Default: at most 5 automatic sidecar restarts...
+pub const DEFAULT_SIDECAR_RESTART_MAX_ATTEMPTS: u32 = 5;
+/// ...within 5 minutes
+pub const DEFAULT_SIDECAR_RESTART_WINDOW_SECS: u64 = 300;
+/// Default: coalesce SSE delta events within one 16ms frame (0 disables batching)
+pub const DEFAULT_SSE_BATCH_WINDOW_MS: u64 = 16;
+/// Default: report a stalled reply after 30s without agent events (0 disables)
+pub const DEFAULT_SSE_STALL_TIMEOUT_SECS: u64 = 30;
+
+/// Raw `runtimeSettings` section as written in config.json (all fields optional)
+///
+/// # Example JSON
+/// ```json
+/// {
+///   "runtimeSettings": {
+///     "healthCheckMaxAttempts": 150,
+///     "basePort": 42000,
+///     "portRange": 200,
+///     "httpProxyTimeoutSecs": 300
+///   }
+/// }
+/// ```
+#[derive(Debug, Deserialize, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeSettingsConfig {
+    /// Health check attempts (100ms apart) before a starting sidecar is considered failed
+    pub health_check_max_attempts: Option<u32>,
+    /// Seconds to wait for a sidecar to exit gracefully before force killing it
+    pub graceful_shutdown_timeout_secs: Option<u64>,
+    /// First port tried for sidecars
+    pub base_port: Option<u16>,
+    /// Number of ports (starting at base_port) sidecars may use
+    pub port_range: Option<u16>,
+    /// SSE idle timeout (must exceed the 15s heartbeat interval)
+    pub sse_read_timeout_secs: Option<u64>,
+    /// Total timeout for proxied HTTP requests
+    pub http_proxy_timeout_secs: Option<u64>,
+    /// Maximum automatic restarts of a crashed sidecar within the restart window (0 disables)
+    pub sidecar_restart_max_attempts: Option<u32>,
+    /// Sliding window for the restart budget
+    pub sidecar_restart_window_secs: Option<u64>,
+    /// Window for coalescing SSE delta events into `sse:{tab}:batch` (0 disables)
+    pub sse_batch_window_ms: Option<u64>,
+    /// Record every Tab's SSE stream to ~/.myagents/recordings (debugging)
+    pub sse_recording_enabled: Option<bool>,
+    /// Silence (no events, heartbeats excluded) during a reply before `sse:{tab}:stalled` (0 disables)
+    pub sse_stall_timeout_secs: Option<u64>,
+}
+
+/// Validated runtime settings with defaults applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeSettings {
+    pub health_check_max_attempts: u32,
+    pub graceful_shutdown_timeout_secs: u64,
+    pub base_port: u16,
+    pub port_range: u16,
+    pub sse_read_timeout_secs: u64,
+    pub http_proxy_timeout_secs: u64,
+    pub sidecar_restart_max_attempts: u32,
+    pub sidecar_restart_window_secs: u64,
+    pub sse_batch_window_ms: u64,
+    pub sse_recording_enabled: bool,
+    pub sse_stall_timeout_secs: u64,
+}
+
+impl Default for RuntimeSettings {
+    fn default() -> Self {
+        Self {
+            health_check_max_attempts: DEFAULT_HEALTH_CHECK_MAX_ATTEMPTS,
+            graceful_shutdown_timeout_secs: DEFAULT_GRACEFUL_SHUTDOWN_TIMEOUT_SECS,
+            base_port: DEFAULT_BASE_PORT,
+            port_range: DEFAULT_PORT_RANGE,
+            sse_read_timeout_secs: DEFAULT_SSE_READ_TIMEOUT_SECS,
+            http_proxy_timeout_secs: DEFAULT_HTTP_PROXY_TIMEOUT_SECS,
+            sidecar_restart_max_attempts: DEFAULT_SIDECAR_RESTART_MAX_ATTEMPTS,
+            sidecar_restart_window_secs: DEFAULT_SIDECAR_RESTART_WINDOW_SECS,
+            sse_batch_window_ms: DEFAULT_SSE_BATCH_WINDOW_MS,
+            sse_recording_enabled: false,
+            sse_stall_timeout_secs: DEFAULT_SSE_STALL_TIMEOUT_SECS,
+        }
+    }
+}
+
+/// Validate an optional value against an inclusive range
+/// Returns the default (and records an error) if the value is out of range
+fn checked<T>(
+    errors: &mut Vec<String>,
+    field: &str,
+    value: Option<T>,
+    min: T,
+    max: T,
+    default: T,
+) -> T
+where
+    T: PartialOrd + Copy + std::fmt::Display,
+{
+    match value {
+        None => default,
+        Some(v) if v >= min && v <= max => v,
+        Some(v) => {
+            errors.push(format!(
+                "runtimeSettings.{} = {} is out of range ({}-{}), using default {}",
+                field, v, min, max, default
+            ));
+            default
+        }
+    }
+}
+
+impl RuntimeSettings {
+    /// Validate raw settings, returning the effective settings and a list of problems
+    pub fn resolve(raw: &RuntimeSettingsConfig) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let d = Self::default();
+
+        let mut settings = Self {
+            health_check_max_attempts: checked(
+                &mut errors, "healthCheckMaxAttempts",
+                raw.health_check_max_attempts, 10, 3000, d.health_check_max_attempts,
+            ),
+            graceful_shutdown_timeout_secs: checked(
+                &mut errors, "gracefulShutdownTimeoutSecs",
+                raw.graceful_shutdown_timeout_secs, 1, 60, d.graceful_shutdown_timeout_secs,
+            ),
+            base_port: checked(
+                &mut errors, "basePort",
+                raw.base_port, 1024, 65535, d.base_port,
+            ),
+            port_range: checked(
+                &mut errors, "portRange",
+                raw.port_range, 1, 10000, d.port_range,
+            ),
+            // Must stay above the backend's 15s heartbeat interval
+            sse_read_timeout_secs: checked(
+                &mut errors, "sseReadTimeoutSecs",
+                raw.sse_read_timeout_secs, 20, 3600, d.sse_read_timeout_secs,
+            ),
+            http_proxy_timeout_secs: checked(
+                &mut errors, "httpProxyTimeoutSecs",
+                raw.http_proxy_timeout_secs, 5, 3600, d.http_proxy_timeout_secs,
+            ),
+            sidecar_restart_max_attempts: checked(
+                &mut errors, "sidecarRestartMaxAttempts",
+                raw.sidecar_restart_max_attempts, 0, 100, d.sidecar_restart_max_attempts,
+            ),
+            sidecar_restart_window_secs: checked(
+                &mut errors, "sidecarRestartWindowSecs",
+                raw.sidecar_restart_window_secs, 10, 86400, d.sidecar_restart_window_secs,
+            ),
+            sse_batch_window_ms: checked(
+                &mut errors, "sseBatchWindowMs",
+                raw.sse_batch_window_ms, 0, 1000, d.sse_batch_window_ms,
+            ),
+            sse_recording_enabled: raw.sse_recording_enabled.unwrap_or(d.sse_recording_enabled),
+            sse_stall_timeout_secs: checked(
+                &mut errors, "sseStallTimeoutSecs",
+                raw.sse_stall_timeout_secs, 0, 3600, d.sse_stall_timeout_secs,
+            ),
+        };
+
+        // The port range must fit below 65535
+        if u32::from(settings.base_port) + u32::from(settings.port_range) > u32::from(u16::MAX) {
+            errors.push(format!(
+                "runtimeSettings.basePort + portRange ({} + {}) exceeds 65535, using defaults {} + {}",
+                settings.base_port, settings.port_range, d.base_port, d.port_range
+            ));
+            settings.base_port = d.base_port;
+            settings.port_range = d.port_range;
+        }
+
+        (settings, errors)
+    }
+
+    /// Last port sidecars may use
+    pub fn port_range_end(&self) -> u16 {
+        self.base_port.saturating_add(self.port_range)
+    }
+}
+
+/// Typed view of `~/.myagents/config.json` (only the sections Rust uses)
+#[derive(Debug, Clone, Default)]
+pub struct AppConfig {
+    /// Proxy settings as configured (may be disabled)
+    pub proxy_settings: Option<ProxySettings>,
+    /// TLS trust settings (defaults when missing)
+    pub tls_settings: TlsSettings,
+    /// Validated runtime settings
+    pub runtime: RuntimeSettings,
+    /// Extra origins the HTTP proxy may fetch
+    pub http_proxy_policy: HttpProxyPolicySettings,
+    /// Full file contents, used to compute change diffs
+    pub raw: serde_json::Value,
+}
+
+impl AppConfig {
+    /// Build config from parsed JSON. Each section is parsed independently so an
+    /// invalid section only falls back to its own defaults.
+    pub fn from_json(json: &serde_json::Value) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+
+        let proxy_settings = section::<ProxySettings>(json, "proxySettings", &mut errors);
+        let tls_settings = section::<TlsSettings>(json, "tlsSettings", &mut errors).unwrap_or_default();
+        let raw_runtime = section::<RuntimeSettingsConfig>(json, "runtimeSettings", &mut errors)
+            .unwrap_or_default();
+        let (runtime, runtime_errors) = RuntimeSettings::resolve(&raw_runtime);
+        errors.extend(runtime_errors);
+        let http_proxy_policy = section::<HttpProxyPolicySettings>(json, "httpProxyPolicy", &mut errors)
+            .unwrap_or_default();
+
+        (Self { proxy_settings, tls_settings, runtime, http_proxy_policy, raw: json.clone() }, errors)
+    }
+}
+
+/// Parse one top-level section, recording an error if it has the wrong shape
+fn section<T: serde::de::DeserializeOwned>(
+    json: &serde_json::Value,
+    key: &str,
+    errors: &mut Vec<String>,
+) -> Option<T> {
+    let value = json.get(key).filter(|v| !v.is_null())?;
+    match serde_json::from_value(value.clone()) {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            errors.push(format!("Invalid \"{}\" section: {}. Using defaults.", key, e));
+            None
+        }
+    }
+}
+
+/// Path of the app config file (~/.myagents/config.json)
+pub fn config_file_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".myagents").join("config.json"))
+}
+
+/// Read and validate a config file
+/// A missing file yields defaults; unreadable files and invalid JSON are errors.
+/// On success, also returns the per-section validation problems.
+fn read_config_file(config_path: &Path) -> Result<(AppConfig, Vec<String>), String> {
+    // Read config file
+    let content = match fs::read_to_string(config_path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            // File not existing is normal (first run)
+            return Ok((AppConfig::default(), Vec::new()));
+        }
+        Err(e) => {
+            return Err(format!(
+                "Failed to read config file {:?}: {}. Check file permissions.",
+                config_path, e
+            ));
+        }
+    };
+
+    // Parse JSON
+    let json: serde_json::Value = serde_json::from_str(&content).map_err(|e| {
+        format!(
+            "Invalid JSON in {:?}: {}. Please check the configuration file format.",
+            config_path, e
+        )
+    })?;
+
+    Ok(AppConfig::from_json(&json))
+}
+
+/// Load and validate ~/.myagents/config.json from disk
+/// Never fails: a missing or invalid file yields defaults, with errors logged
+pub fn load_app_config() -> AppConfig {
+    let Some(config_path) = config_file_path() else {
+        return AppConfig::default();
+    };
+
+    match read_config_file(&config_path) {
+        Ok((config, errors)) => {
+            for error in errors {
+                log::error!("[app_config] {:?}: {}", config_path, error);
+            }
+            config
+        }
+        Err(e) => {
+            log::error!("[app_config] {}", e);
+            AppConfig::default()
+        }
+    }
+}
+
+/// Cached config, kept up to date by the config watcher
+static CURRENT: RwLock<Option<Arc<AppConfig>>> = RwLock::new(None);
+
+/// Current app config (loaded from disk on first use, then refreshed by the watcher)
+pub fn current() -> Arc<AppConfig> {
+    if let Some(config) = CURRENT.read().ok().and_then(|guard| guard.clone()) {
+        return config;
+    }
+
+    let loaded = Arc::new(load_app_config());
+    match CURRENT.write() {
+        Ok(mut guard) => guard.get_or_insert(loaded).clone(),
+        Err(_) => loaded,
+    }
+}
+
+/// Replace the cached config
+fn replace_current(config: Arc<AppConfig>) {
+    if let Ok(mut guard) = CURRENT.write() {
+        *guard = Some(config);
+    }
+}
+
+/// Effective runtime settings (defaults merged with config.json)
+pub fn runtime_settings() -> RuntimeSettings {
+    current().runtime.clone()
+}
+
+// ============= Change Detection =============
+
+/// A validated change of config.json
+pub struct ConfigChange {
+    /// Dotted paths of changed values, e.g. `proxySettings.enabled`
+    pub changed_paths: Vec<String>,
+}
+
+impl ConfigChange {
+    /// Whether any changed path is `prefix` or lies below it
+    pub fn touches(&self, prefix: &str) -> bool {
+        self.changed_paths.iter().any(|path| {
+            path == prefix
+                || path
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
+}
+
+/// Payload of the `config:changed` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChangedEvent {
+    /// Dotted paths of changed values (values are omitted, they may contain secrets)
+    pub changed_paths: Vec<String>,
+    /// Validation problems in the new config (invalid values fell back to defaults)
+    pub errors: Vec<String>,
+}
+
+/// Payload of the `config:invalid` event (the previous config stays in effect)
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigInvalidEvent {
+    pub error: String,
+}
+
+/// Dotted paths whose values differ between two JSON documents
+/// Objects are compared key by key; any other value (including arrays) is a leaf
+pub fn diff_json_paths(old: &serde_json::Value, new: &serde_json::Value) -> Vec<String> {
+    fn walk(prefix: &str, old: Option<&serde_json::Value>, new: Option<&serde_json::Value>, out: &mut Vec<String>) {
+        match (old, new) {
+            (Some(serde_json::Value::Object(a)), Some(serde_json::Value::Object(b))) => {
+                let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+                keys.sort();
+                keys.dedup();
+                for key in keys {
+                    let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                    walk(&path, a.get(key), b.get(key), out);
+                }
+            }
+            (a, b) if a != b => out.push(prefix.to_string()),
+            _ => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    walk("", Some(old), Some(new), &mut out);
+    out
+}
+
+/// File signature used to detect edits (modification time + size)
+fn file_signature(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Watch config.json for changes
+/// Edits are debounced, re-validated and published as a `config:changed` event;
+/// `on_change` lets subsystems react (rebuild clients, restart sidecars...).
+/// Invalid JSON keeps the previous config in effect and emits `config:invalid`.
+pub fn spawn_config_watcher<R, F>(app_handle: AppHandle<R>, on_change: F)
+where
+    R: Runtime,
+    F: Fn(&ConfigChange) + Send + 'static,
+{
+    let Some(config_path) = config_file_path() else {
+        log::warn!("[app_config] No home directory, config watcher disabled");
+        return;
+    };
+
+    // Make sure the cache is primed before the first comparison
+    let _ = current();
+
+    let result = thread::Builder::new()
+        .name("config-watcher".to_string())
+        .spawn(move || {
+            let mut last_signature = file_signature(&config_path);
+            let mut pending_since: Option<Instant> = None;
+
+            loop {
+                thread::sleep(Duration::from_millis(WATCH_POLL_INTERVAL_MS));
+
+                let signature = file_signature(&config_path);
+                if signature != last_signature {
+                    // Still being written: restart the debounce window
+                    last_signature = signature;
+                    pending_since = Some(Instant::now());
+                    continue;
+                }
+
+                match pending_since {
+                    Some(since) if since.elapsed() >= Duration::from_millis(WATCH_DEBOUNCE_MS) => {
+                        pending_since = None;
+                        reload_config(&app_handle, &config_path, &on_change);
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+    if let Err(e) = result {
+        log::error!("[app_config] Failed to spawn config watcher: {}", e);
+    }
+}
+
+/// Reload config.json after an edit and publish the change
+fn reload_config<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    config_path: &Path,
+    on_change: &dyn Fn(&ConfigChange),
+) {
+    let (config, errors) = match read_config_file(config_path) {
+        Ok(result) => result,
+        Err(error) => {
+            log::error!("[app_config] {} Keeping previous configuration.", error);
+            let _ = app_handle.emit("config:invalid", ConfigInvalidEvent { error });
+            return;
+        }
+    };
+    for error in &errors {
+        log::error!("[app_config] {:?}: {}", config_path, error);
+    }
+
+    let changed_paths = diff_json_paths(&current_raw(), &config.raw);
+    if changed_paths.is_empty() {
+        return;
+    }
+
+    replace_current(Arc::new(config));
+    log::info!("[app_config] config.json changed: {}", changed_paths.join(", "));
+
+    let event = ConfigChangedEvent {
+        changed_paths: changed_paths.clone(),
+        errors,
+    };
+    if let Err(e) = app_handle.emit("config:changed", &event) {
+        log::error!("[app_config] Failed to emit config:changed: {}", e);
+    }
+
+    on_change(&ConfigChange { changed_paths });
+}
+
+/// Raw JSON of the cached config
+fn current_raw() -> serde_json::Value {
+    current().raw.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_defaults_when_section_missing() {
+        let (config, errors) = AppConfig::from_json(&json!({ "theme": "dark" }));
+        assert!(errors.is_empty());
+        assert!(config.proxy_settings.is_none());
+        assert_eq!(config.runtime, RuntimeSettings::default());
+    }
+
+    #[test]
+    fn test_valid_overrides_are_applied() {
+        let (config, errors) = AppConfig::from_json(&json!({
+            "runtimeSettings": {
+                "healthCheckMaxAttempts": 150,
+                "basePort": 42000,
+                "portRange": 200,
+                "httpProxyTimeoutSecs": 300,
+                "sseBatchWindowMs": 0,
+                "sseStallTimeoutSecs": 0
+            }
+        }));
+        assert!(errors.is_empty());
+        assert_eq!(config.runtime.health_check_max_attempts, 150);
+        assert_eq!(config.runtime.base_port, 42000);
+        assert_eq!(config.runtime.port_range_end(), 42200);
+        assert_eq!(config.runtime.http_proxy_timeout_secs, 300);
+        assert_eq!(config.runtime.sse_batch_window_ms, 0);
+        assert_eq!(config.runtime.sse_stall_timeout_secs, 0);
+        assert_eq!(config.runtime.sse_read_timeout_secs, DEFAULT_SSE_READ_TIMEOUT_SECS);
+    }
+
+    #[test]
+    fn test_out_of_range_value_falls_back_to_default() {
+        let (config, errors) = AppConfig::from_json(&json!({
+            "runtimeSettings": { "sseReadTimeoutSecs": 10, "gracefulShutdownTimeoutSecs": 3 }
+        }));
+        assert_eq!(config.runtime.sse_read_timeout_secs, DEFAULT_SSE_READ_TIMEOUT_SECS);
+        assert_eq!(config.runtime.graceful_shutdown_timeout_secs, 3);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("sseReadTimeoutSecs = 10 is out of range"));
+    }
+
+    #[test]
+    fn test_port_range_overflow_is_rejected() {
+        let (config, errors) = AppConfig::from_json(&json!({
+            "runtimeSettings": { "basePort": 65000, "portRange": 1000 }
+        }));
+        assert_eq!(config.runtime.base_port, DEFAULT_BASE_PORT);
+        assert_eq!(config.runtime.port_range, DEFAULT_PORT_RANGE);
+        assert!(errors[0].contains("exceeds 65535"));
+    }
+
+    #[test]
+    fn test_diff_json_paths_reports_nested_changes() {
+        let old = json!({
+            "theme": "dark",
+            "proxySettings": { "enabled": false, "host": "127.0.0.1", "port": 7890 },
+            "mcpEnabledServers": ["a"]
+        });
+        let new = json!({
+            "theme": "dark",
+            "proxySettings": { "enabled": true, "host": "127.0.0.1" },
+            "mcpEnabledServers": ["a", "b"],
+            "runtimeSettings": { "basePort": 42000 }
+        });
+
+        assert_eq!(
+            diff_json_paths(&old, &new),
+            vec![
+                "mcpEnabledServers",
+                "proxySettings.enabled",
+                "proxySettings.port",
+                "runtimeSettings",
+            ]
+        );
+        assert!(diff_json_paths(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_config_change_touches_prefix() {
+        let change = ConfigChange {
+            changed_paths: vec!["proxySettings.enabled".to_string(), "themeMode".to_string()],
+        };
+        assert!(change.touches("proxySettings"));
+        assert!(change.touches("proxySettings.enabled"));
+        assert!(!change.touches("proxySettings.host"));
+        assert!(!change.touches("theme"));
+    }
+
+    #[test]
+    fn test_invalid_section_does_not_affect_others() {
+        let (config, errors) = AppConfig::from_json(&json!({
+            "proxySettings": { "enabled": true, "port": 8080 },
+            "runtimeSettings": { "basePort": "not-a-number" }
+        }));
+        assert_eq!(config.runtime, RuntimeSettings::default());
+        assert_eq!(config.proxy_settings.and_then(|p| p.port), Some(8080));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("Invalid \"runtimeSettings\" section"));
+    }
+}
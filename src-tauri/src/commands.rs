@@ -2,6 +2,7 @@
 // Supports both legacy single-instance and new multi-instance APIs
 
 use std::path::PathBuf;
+use std::sync::Arc;
 use tauri::{AppHandle, Runtime, State};
 
 use crate::sidecar::{
@@ -12,8 +13,12 @@ use crate::sidecar::{
     // New multi-instance exports
     start_tab_sidecar, stop_tab_sidecar, get_tab_server_url, get_tab_sidecar_status,
     start_global_sidecar, stop_all_sidecars, GLOBAL_SIDECAR_ID,
+    restart_sidecars_with_stale_network_env,
 };
+use crate::http_clients::HttpClientRegistry;
+use crate::http_proxy::{cancel_requests_for_port, HttpProxyState};
 use crate::logger;
+use crate::proxy_config;
 
 // ============= Legacy Commands (for backward compatibility) =============
 
@@ -136,7 +141,17 @@ pub async fn cmd_start_tab_sidecar<R: Runtime>(
 
     let agent_path = agent_dir.map(PathBuf::from);
 
-    match start_tab_sidecar(&app_handle, &state, &tab_id, agent_path) {
+    // Startup blocks (PAC evaluation, port handshake, health checks): keep it off the async runtime
+    let manager = state.inner().clone();
+    let start_app = app_handle.clone();
+    let start_tab = tab_id.clone();
+    let started = tauri::async_runtime::spawn_blocking(move || {
+        start_tab_sidecar(&start_app, &manager, &start_tab, agent_path)
+    })
+    .await
+    .map_err(|e| format!("Sidecar start task failed: {}", e))?;
+
+    match started {
         Ok(port) => {
             let status = get_tab_sidecar_status(&state, &tab_id)?;
             logger::info(&app_handle, format!("[sidecar] Tab {} started on port {}", tab_id, port));
@@ -154,10 +169,17 @@ pub async fn cmd_start_tab_sidecar<R: Runtime>(
 pub async fn cmd_stop_tab_sidecar(
     app_handle: AppHandle,
     state: State<'_, ManagedSidecar>,
+    http_state: State<'_, Arc<HttpProxyState>>,
     tab_id: String,
 ) -> Result<(), String> {
     logger::info(&app_handle, format!("[sidecar] Stopping tab {}", tab_id));
-    stop_tab_sidecar(&state, &tab_id)
+    let port = get_tab_sidecar_status(&state, &tab_id)?.port;
+    stop_tab_sidecar(&state, &tab_id)?;
+    // Requests to the stopped sidecar can only fail now; don't leave them hanging
+    if port != 0 {
+        cancel_requests_for_port(&app_handle, &http_state, port);
+    }
+    Ok(())
 }
 
 /// Command: Get server URL for a specific Tab
@@ -320,3 +342,39 @@ pub fn cmd_get_device_id() -> Result<String, String> {
 
     Ok(new_id)
 }
+
+// ============= Proxy Credentials =============
+
+/// Command: Save (or clear with None/empty) the proxy password
+/// Stored in ~/.myagents/credentials.json (mode 0600), never in config.json.
+/// Running sidecars are restarted and shared HTTP clients rebuilt so the new credentials apply.
+#[tauri::command]
+pub async fn cmd_set_proxy_password<R: Runtime>(
+    app_handle: AppHandle<R>,
+    state: State<'_, ManagedSidecar>,
+    http_clients: State<'_, Arc<HttpClientRegistry>>,
+    password: Option<String>,
+) -> Result<(), String> {
+    let cleared = !password.as_deref().is_some_and(|p| !p.is_empty());
+    // File I/O and sidecar restarts (which may evaluate PAC) block: keep them off the async runtime
+    let manager = state.inner().clone();
+    let http_clients = http_clients.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        proxy_config::save_proxy_password(password.as_deref())?;
+        http_clients.invalidate();
+        logger::info(
+            &app_handle,
+            format!("[proxy] Proxy password {}", if cleared { "cleared" } else { "saved" }),
+        );
+        restart_sidecars_with_stale_network_env(&app_handle, &manager);
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Proxy password task failed: {}", e))?
+}
+
+/// Command: Whether a proxy password is saved (the password itself is never returned)
+#[tauri::command]
+pub fn cmd_has_proxy_password() -> bool {
+    proxy_config::read_proxy_password().is_some()
+}
@@ -0,0 +1,350 @@
+// HTTP client registry - one pooled reqwest client per purpose, shared by all calls
+//
+// Building a reqwest::Client per request throws away its connection pool and TLS
+// state. Clients are built on first use and cached until config.json changes
+// (proxySettings / tlsSettings / runtimeSettings) or the proxy password changes.
+//
+// Purposes:
+// - Loopback: HTTP proxy to sidecars (buffered and streamed; total timeouts are set
+//   per request, the client only carries the idle timeout)
+// - Sse: long-lived sidecar SSE streams (sseReadTimeoutSecs idle timeout)
+// - External: outbound requests through the user's proxy and TLS settings
+// - Allowlisted: HTTP proxy to allowlisted origins; External's settings plus a redirect
+//   policy that re-checks every hop (see http_policy.rs)
+//
+// Metrics: requests are counted as they are sent (http_clients::send, so every retry
+// attempt counts) and every client counts the connections its connector opens, so
+// `requests - connectionsOpened` is the number of requests served from the pool.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::app_config;
+use crate::http_policy;
+use crate::logger;
+use crate::proxy_config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ClientPurpose {
+    Loopback,
+    Sse,
+    External,
+    Allowlisted,
+}
+
+impl ClientPurpose {
+    const ALL: [ClientPurpose; 4] = [
+        ClientPurpose::Loopback,
+        ClientPurpose::Sse,
+        ClientPurpose::External,
+        ClientPurpose::Allowlisted,
+    ];
+}
+
+/// Counters for one purpose (kept across rebuilds)
+#[derive(Default)]
+struct PurposeCounters {
+    requests: AtomicU64,
+    connections_opened: Arc<AtomicU64>,
+    builds: AtomicU64,
+}
+
+/// Snapshot returned by get_http_client_metrics
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientMetrics {
+    pub purpose: ClientPurpose,
+    /// Requests sent with this client (each retry attempt counts)
+    pub requests: u64,
+    /// New connections opened by its connector
+    pub connections_opened: u64,
+    /// Requests served over an already open (pooled) connection
+    pub reused_requests: u64,
+    /// Times the client was (re)built
+    pub builds: u64,
+}
+
+/// Managed state: cached clients plus their metrics
+pub struct HttpClientRegistry {
+    clients: Mutex<HashMap<ClientPurpose, reqwest::Client>>,
+    counters: HashMap<ClientPurpose, PurposeCounters>,
+}
+
+impl Default for HttpClientRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpClientRegistry {
+    pub fn new() -> Self {
+        Self {
+            clients: Mutex::new(HashMap::new()),
+            counters: ClientPurpose::ALL.iter().map(|p| (*p, PurposeCounters::default())).collect(),
+        }
+    }
+
+    /// Cached client for `purpose`, built on first use
+    /// `warn` reports redirects the Allowlisted client refuses to follow (see http_policy.rs)
+    pub fn get(
+        &self,
+        purpose: ClientPurpose,
+        warn: impl Fn(String) + Send + Sync + 'static,
+    ) -> Result<reqwest::Client, String> {
+        let counters = &self.counters[&purpose];
+        let mut clients = self.clients.lock().map_err(|e| e.to_string())?;
+        if let Some(client) = clients.get(&purpose) {
+            return Ok(client.clone());
+        }
+        let client = build_client(purpose, Arc::clone(&counters.connections_opened), warn)?;
+        counters.builds.fetch_add(1, Ordering::Relaxed);
+        log::info!("[http-clients] Built {:?} client", purpose);
+        clients.insert(purpose, client.clone());
+        Ok(client)
+    }
+
+    /// Count one request sent with the `purpose` client
+    pub fn count_request(&self, purpose: ClientPurpose) {
+        self.counters[&purpose].requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Drop all cached clients; the next request rebuilds them from the current config
+    /// (requests already in flight keep their client until they finish)
+    pub fn invalidate(&self) {
+        if let Ok(mut clients) = self.clients.lock() {
+            if !clients.is_empty() {
+                log::info!("[http-clients] Settings changed, rebuilding {} client(s) on next use", clients.len());
+                clients.clear();
+            }
+        }
+    }
+
+    /// Rebuild clients if a change touches settings they are built from
+    pub fn apply_config_change(&self, change: &app_config::ConfigChange) {
+        if change.touches("proxySettings") || change.touches("tlsSettings") || change.touches("runtimeSettings") {
+            self.invalidate();
+        }
+    }
+
+    pub fn metrics(&self) -> Vec<ClientMetrics> {
+        ClientPurpose::ALL
+            .iter()
+            .map(|purpose| {
+                let counters = &self.counters[purpose];
+                let requests = counters.requests.load(Ordering::Relaxed);
+                let connections_opened = counters.connections_opened.load(Ordering::Relaxed);
+                ClientMetrics {
+                    purpose: *purpose,
+                    requests,
+                    connections_opened,
+                    reused_requests: requests.saturating_sub(connections_opened),
+                    builds: counters.builds.load(Ordering::Relaxed),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Shared client for `purpose` from the app's registry
+pub fn client<R: Runtime>(app: &AppHandle<R>, purpose: ClientPurpose) -> Result<reqwest::Client, String> {
+    let warn_app = app.clone();
+    let warn = move |message: String| logger::warn(&warn_app, message);
+    app.state::<Arc<HttpClientRegistry>>().get(purpose, warn).map_err(|e| {
+        let err = format!("[http-clients] Failed to create {:?} client: {}", purpose, e);
+        logger::error(app, &err);
+        err
+    })
+}
+
+/// Send `request`, built from the `purpose` client, and count it in the metrics
+pub async fn send<R: Runtime>(
+    app: &AppHandle<R>,
+    purpose: ClientPurpose,
+    request: reqwest::RequestBuilder,
+) -> reqwest::Result<reqwest::Response> {
+    app.state::<Arc<HttpClientRegistry>>().count_request(purpose);
+    request.send().await
+}
+
+/// Get ready to send a request for `url` with the `purpose` client
+/// (External and Allowlisted: resolves the PAC proxy off the async runtime, see proxy_config.rs)
+pub async fn prepare_request(purpose: ClientPurpose, url: &str) {
+    if !matches!(purpose, ClientPurpose::External | ClientPurpose::Allowlisted) {
+        return;
+    }
+    if let Ok(url) = reqwest::Url::parse(url) {
+        proxy_config::prepare_external_request(&url).await;
+    }
+}
+
+fn build_client(
+    purpose: ClientPurpose,
+    connections: Arc<AtomicU64>,
+    warn: impl Fn(String) + Send + Sync + 'static,
+) -> Result<reqwest::Client, String> {
+    let runtime = app_config::runtime_settings();
+    let builder = reqwest::Client::builder().connector_layer(CountConnectionsLayer { connections });
+    let builder = match purpose {
+        // Enable tcp_nodelay to disable Nagle's algorithm for faster response times
+        // Force HTTP/1.1 for compatibility with Bun server (HTTP/2 may cause connection issues on Windows)
+        // CRITICAL: Disable proxy for localhost - reqwest uses system proxy by default!
+        ClientPurpose::Loopback => builder
+            .read_timeout(Duration::from_secs(runtime.http_proxy_timeout_secs))
+            .tcp_nodelay(true)
+            .http1_only()  // Force HTTP/1.1 for stability (TODO v0.1.8: test HTTP/2 negotiation)
+            .pool_idle_timeout(Duration::from_secs(5))  // Recycle idle connections after 5s
+            .pool_max_idle_per_host(8)  // Per sidecar port; settings pages fire bursts of calls
+            .redirect(reqwest::redirect::Policy::none())  // Never leave the sidecar (see http_policy.rs)
+            .no_proxy(),  // Disable proxy for all requests (especially localhost)
+        // IMPORTANT: Do NOT use timeout() which is total request time - SSE connections are meant to be long-lived
+        // Use read_timeout instead: if no data received within this time, connection is considered dead
+        // Backend sends heartbeat every 15s, so the default 60s read_timeout gives 4x margin
+        // CRITICAL: Enable tcp_nodelay to disable Nagle's algorithm for immediate packet transmission
+        // Without this, small SSE events may be buffered and delayed, causing UI to feel unresponsive
+        ClientPurpose::Sse => builder
+            .read_timeout(Duration::from_secs(runtime.sse_read_timeout_secs))
+            .tcp_nodelay(true)
+            .http1_only()
+            .pool_idle_timeout(Duration::from_secs(5))
+            .pool_max_idle_per_host(2)
+            .no_proxy(),
+        ClientPurpose::External => return proxy_config::build_client_with_proxy(builder),
+        ClientPurpose::Allowlisted => {
+            return proxy_config::build_client_with_proxy(builder.redirect(http_policy::redirect_policy(warn)))
+        }
+    };
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// Connector layer counting every new connection
+#[derive(Clone)]
+struct CountConnectionsLayer {
+    connections: Arc<AtomicU64>,
+}
+
+impl<S> tower_layer::Layer<S> for CountConnectionsLayer {
+    type Service = CountConnections<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CountConnections { inner, connections: Arc::clone(&self.connections) }
+    }
+}
+
+#[derive(Clone)]
+struct CountConnections<S> {
+    inner: S,
+    connections: Arc<AtomicU64>,
+}
+
+impl<S, Request> tower_service::Service<Request> for CountConnections<S>
+where
+    S: tower_service::Service<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+        self.inner.call(request)
+    }
+}
+
+/// Command: pool reuse metrics per client purpose
+#[tauri::command]
+pub fn get_http_client_metrics(
+    state: tauri::State<'_, Arc<HttpClientRegistry>>,
+) -> Vec<ClientMetrics> {
+    state.metrics()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// Keep-alive HTTP server answering every request on a connection with "ok"
+    fn spawn_keep_alive_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                std::thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut stream = stream;
+                    loop {
+                        let mut content_length = 0;
+                        loop {
+                            let mut line = String::new();
+                            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                                return;
+                            }
+                            if line == "\r\n" {
+                                break;
+                            }
+                            if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                                content_length = v.trim().parse().unwrap_or(0);
+                            }
+                        }
+                        let mut body = vec![0; content_length];
+                        let _ = reader.read_exact(&mut body);
+                        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn test_clients_are_cached_until_invalidated() {
+        let registry = HttpClientRegistry::new();
+        registry.get(ClientPurpose::Loopback, |_| {}).unwrap();
+        registry.get(ClientPurpose::Loopback, |_| {}).unwrap();
+        registry.get(ClientPurpose::Sse, |_| {}).unwrap();
+        registry.invalidate();
+        registry.get(ClientPurpose::Loopback, |_| {}).unwrap();
+
+        let metrics = registry.metrics();
+        let loopback = metrics.iter().find(|m| m.purpose == ClientPurpose::Loopback).unwrap();
+        // Getting a client is not a request
+        assert_eq!((loopback.requests, loopback.builds), (0, 2));
+        let sse = metrics.iter().find(|m| m.purpose == ClientPurpose::Sse).unwrap();
+        assert_eq!((sse.requests, sse.builds), (0, 1));
+        let external = metrics.iter().find(|m| m.purpose == ClientPurpose::External).unwrap();
+        assert_eq!(external.builds, 0);
+    }
+
+    #[test]
+    fn test_sequential_requests_reuse_one_connection() {
+        let port = spawn_keep_alive_server();
+        let registry = HttpClientRegistry::new();
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            // One client for all attempts, like send_with_retries
+            let client = registry.get(ClientPurpose::Loopback, |_| {}).unwrap();
+            for _ in 0..5 {
+                registry.count_request(ClientPurpose::Loopback);
+                let response = client.get(format!("http://127.0.0.1:{}/api/skills", port)).send().await.unwrap();
+                assert_eq!(response.text().await.unwrap(), "ok");
+            }
+        });
+
+        let loopback = registry.metrics().into_iter().find(|m| m.purpose == ClientPurpose::Loopback).unwrap();
+        assert_eq!(loopback.requests, 5);
+        assert_eq!(loopback.connections_opened, 1);
+        assert_eq!(loopback.reused_requests, 4);
+    }
+}
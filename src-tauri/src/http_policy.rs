@@ -0,0 +1,286 @@
+// HTTP proxy policy - which URLs the HTTP proxy may fetch (SSRF guard)
+//
+// The WebView can hand proxy_http_request any URL, and the request is sent from Rust,
+// outside the WebView's CSP. A script injected through rendered agent output could
+// otherwise use it to reach hosts on the internal network. Allowed targets:
+// - http://127.0.0.1:{port} where {port} belongs to a SidecarInstance in SidecarManager
+// - the app's own services (DEFAULT_ALLOWED_ORIGINS, e.g. analytics)
+// - origins listed in config.json `httpProxyPolicy.allowedOrigins`
+//
+// Sidecar targets use the Loopback client (no proxy, no redirects). Allowlisted origins
+// use the Allowlisted client (user's proxy and TLS settings) whose redirect policy
+// re-checks every hop, so an allowlisted origin cannot bounce a request to an internal
+// host or to a sidecar.
+
+use serde::Deserialize;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::app_config;
+use crate::http_clients::ClientPurpose;
+use crate::logger;
+use crate::sidecar::ManagedSidecarManager;
+
+/// Origins the frontend itself talks to, allowed without configuration
+/// (src/renderer/analytics/config.ts sends events through proxyFetch)
+const DEFAULT_ALLOWED_ORIGINS: &[&str] = &["https://analytics.myagents.io"];
+/// Redirect hops followed for allowlisted requests (reqwest's default limit)
+const MAX_REDIRECTS: usize = 10;
+
+/// `httpProxyPolicy` section of `~/.myagents/config.json`
+///
+/// # Example JSON
+/// ```json
+/// {
+///   "httpProxyPolicy": {
+///     "allowedOrigins": ["https://api.example.com", "http://10.0.0.5:8080"]
+///   }
+/// }
+/// ```
+#[derive(Debug, Deserialize, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HttpProxyPolicySettings {
+    /// Origins (`scheme://host[:port]`) the proxy may fetch in addition to
+    /// DEFAULT_ALLOWED_ORIGINS; bare hosts mean https
+    pub allowed_origins: Vec<String>,
+}
+
+/// Where an allowed request goes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProxyTarget {
+    /// A running sidecar on this port
+    Sidecar(u16),
+    /// A built-in origin or one from `httpProxyPolicy.allowedOrigins`
+    Allowlisted,
+}
+
+impl ProxyTarget {
+    pub fn client_purpose(self) -> ClientPurpose {
+        match self {
+            ProxyTarget::Sidecar(_) => ClientPurpose::Loopback,
+            ProxyTarget::Allowlisted => ClientPurpose::Allowlisted,
+        }
+    }
+}
+
+/// Normalize an allowlist entry to a serialized origin (None if unparseable)
+fn normalize_origin(entry: &str) -> Option<String> {
+    let entry = entry.trim().trim_end_matches('/');
+    if entry.is_empty() {
+        return None;
+    }
+    let url = if entry.contains("://") {
+        reqwest::Url::parse(entry).ok()?
+    } else {
+        reqwest::Url::parse(&format!("https://{}", entry)).ok()?
+    };
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    Some(url.origin().ascii_serialization())
+}
+
+/// Built-in origins followed by the configured ones
+fn effective_allowed_origins(configured: &[String]) -> Vec<String> {
+    DEFAULT_ALLOWED_ORIGINS
+        .iter()
+        .map(|origin| origin.to_string())
+        .chain(configured.iter().cloned())
+        .collect()
+}
+
+/// Decide whether `url` may be fetched; `owns_port` tells if a sidecar uses a port
+pub fn check_url(
+    url: &str,
+    owns_port: impl Fn(u16) -> bool,
+    allowed_origins: &[String],
+) -> Result<ProxyTarget, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid URL ({})", e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("scheme {} is not allowed", parsed.scheme()));
+    }
+
+    if parsed.scheme() == "http" && parsed.host_str() == Some("127.0.0.1") {
+        if let Some(port) = parsed.port() {
+            if owns_port(port) {
+                return Ok(ProxyTarget::Sidecar(port));
+            }
+        }
+    }
+
+    let origin = parsed.origin().ascii_serialization();
+    if allowed_origins.iter().filter_map(|entry| normalize_origin(entry)).any(|o| o == origin) {
+        return Ok(ProxyTarget::Allowlisted);
+    }
+    Err(format!("{} is neither a running sidecar nor in httpProxyPolicy.allowedOrigins", origin))
+}
+
+/// Check `url` against the running sidecars and the configured allowlist
+/// Rejections are logged as warnings.
+pub fn authorize<R: Runtime>(app: &AppHandle<R>, url: &str) -> Result<ProxyTarget, String> {
+    let manager = app.state::<ManagedSidecarManager>();
+    let owns_port = |port: u16| manager.lock().map(|m| m.owns_port(port)).unwrap_or(false);
+    let config = app_config::current();
+
+    let allowed_origins = effective_allowed_origins(&config.http_proxy_policy.allowed_origins);
+    check_url(url, owns_port, &allowed_origins).map_err(|reason| {
+        let err = format!("[http-policy] Blocked request to {}: {}", url, reason);
+        logger::warn(app, &err);
+        err
+    })
+}
+
+/// Redirect policy of the Allowlisted client: every hop must be allowlisted itself
+/// (the allowlist is read per hop, so config changes apply without a client rebuild)
+/// Blocked hops are reported through `warn` (logger::warn, like `authorize`).
+pub fn redirect_policy(warn: impl Fn(String) + Send + Sync + 'static) -> reqwest::redirect::Policy {
+    redirect_policy_with(
+        || effective_allowed_origins(&app_config::current().http_proxy_policy.allowed_origins),
+        warn,
+    )
+}
+
+fn redirect_policy_with(
+    allowed_origins: impl Fn() -> Vec<String> + Send + Sync + 'static,
+    warn: impl Fn(String) + Send + Sync + 'static,
+) -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            return attempt.error(format!("more than {} redirects", MAX_REDIRECTS));
+        }
+        // Sidecars are only reachable directly, through the Loopback client
+        match check_url(attempt.url().as_str(), |_| false, &allowed_origins()) {
+            Ok(_) => attempt.follow(),
+            Err(reason) => {
+                let err = format!("[http-policy] Blocked redirect to {}: {}", attempt.url(), reason);
+                warn(err.clone());
+                attempt.error(err)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sidecar_31415(port: u16) -> bool {
+        port == 31415
+    }
+
+    #[test]
+    fn test_only_running_sidecar_ports_are_allowed_by_default() {
+        assert_eq!(
+            check_url("http://127.0.0.1:31415/api/skills", sidecar_31415, &[]),
+            Ok(ProxyTarget::Sidecar(31415))
+        );
+        for url in [
+            "http://127.0.0.1:31416/api/skills",
+            "http://localhost:31415/api/skills",
+            "https://127.0.0.1:31415/api/skills",
+            "http://127.0.0.1/",
+            "http://192.168.1.1:31415/admin",
+            "http://169.254.169.254/latest/meta-data/",
+            "file:///etc/passwd",
+            "not a url",
+        ] {
+            assert!(check_url(url, sidecar_31415, &[]).is_err(), "{} should be blocked", url);
+        }
+    }
+
+    #[test]
+    fn test_allowlisted_origins() {
+        let allowed = vec![
+            "https://api.example.com/".to_string(),
+            "models.example.org".to_string(),
+            "http://10.0.0.5:8080".to_string(),
+            "ftp://files.example.com".to_string(),
+        ];
+        for url in [
+            "https://api.example.com/v1/models",
+            "https://API.example.com:443/v1",
+            "https://models.example.org/list",
+            "http://10.0.0.5:8080/health",
+        ] {
+            assert_eq!(check_url(url, sidecar_31415, &allowed), Ok(ProxyTarget::Allowlisted), "{}", url);
+        }
+        for url in [
+            "http://api.example.com/v1",
+            "https://api.example.com:8443/v1",
+            "https://evil.api.example.com/",
+            "http://10.0.0.5:8081/",
+            "ftp://files.example.com/",
+        ] {
+            assert!(check_url(url, sidecar_31415, &allowed).is_err(), "{} should be blocked", url);
+        }
+    }
+
+    #[test]
+    fn test_builtin_origins_are_always_allowed() {
+        let allowed = effective_allowed_origins(&["https://api.example.com".to_string()]);
+        for url in ["https://analytics.myagents.io/api/track", "https://api.example.com/v1"] {
+            assert_eq!(check_url(url, sidecar_31415, &allowed), Ok(ProxyTarget::Allowlisted), "{}", url);
+        }
+        assert!(check_url("http://analytics.myagents.io/api/track", sidecar_31415, &allowed).is_err());
+    }
+
+    /// HTTP server answering by path; returns its port
+    fn spawn_server(respond: impl Fn(&str) -> String + Send + 'static) -> u16 {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = [0u8; 1024];
+                let n = stream.read(&mut request).unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..n]);
+                let path = request.split(' ').nth(1).unwrap_or("/");
+                let _ = stream.write_all(respond(path).as_bytes());
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn test_redirects_to_other_origins_are_rechecked() {
+        let internal_hits = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let hits = std::sync::Arc::clone(&internal_hits);
+        let internal = spawn_server(move |_| {
+            hits.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            "HTTP/1.1 200 OK\r\nContent-Length: 6\r\nConnection: close\r\n\r\nsecret".to_string()
+        });
+        let allowed = spawn_server(move |path| match path {
+            "/out" => format!(
+                "HTTP/1.1 302 Found\r\nLocation: http://127.0.0.1:{}/admin\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                internal
+            ),
+            "/in" => "HTTP/1.1 302 Found\r\nLocation: /final\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            _ => "HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nfinal".to_string(),
+        });
+
+        let origin = format!("http://127.0.0.1:{}", allowed);
+        let allowlist = vec![origin.clone()];
+        let warnings = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let logged = std::sync::Arc::clone(&warnings);
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .redirect(redirect_policy_with(
+                move || allowlist.clone(),
+                move |message| logged.lock().unwrap().push(message),
+            ))
+            .build()
+            .unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let blocked = client.get(format!("{}/out", origin)).send().await.unwrap_err();
+            assert!(blocked.is_redirect(), "{:?}", blocked);
+
+            let followed = client.get(format!("{}/in", origin)).send().await.unwrap();
+            assert_eq!(followed.text().await.unwrap(), "final");
+        });
+        assert_eq!(internal_hits.load(std::sync::atomic::Ordering::SeqCst), 0);
+        let warnings = warnings.lock().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with(&format!("[http-policy] Blocked redirect to http://127.0.0.1:{}/admin", internal)));
+    }
+}
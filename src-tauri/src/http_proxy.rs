@@ -0,0 +1,1055 @@
+// HTTP Proxy module - Forwards frontend HTTP calls to sidecars through Rust
+// This bypasses WebView CORS restrictions entirely
+//
+// Two variants:
+// - proxy_http_request: buffers the whole response and returns it over IPC
+// - proxy_http_request_stream: returns a request id immediately and emits the body as
+//   `http:{id}:head` / `http:{id}:chunk` / `http:{id}:end` / `http:{id}:error`
+//
+// Those events reach every window. A caller may pass a `channel` instead: the same
+// payloads then go only to its own webview, as HttpStreamMessage values tagged with
+// `type` (tauriClient.ts proxyFetchStream does, so file bodies are not broadcast).
+//
+// Cancellation: every request runs as its own task registered under a request id
+// (caller-supplied or generated). cancel_http_request(id) aborts the task, which drops
+// the reqwest future and closes the connection. Stopping a Tab's sidecar cancels all
+// requests still targeting its port (cancel_requests_for_port).
+//
+// Only running sidecars and allowlisted origins can be reached (see http_policy.rs).
+//
+// Timeouts are configurable via runtimeSettings (see app_config.rs)
+//
+// httpProxyTimeoutSecs: Total timeout for buffered HTTP proxy requests (default 120s)
+// - Allows for slow API responses
+// - Covers model generation time for complex requests
+// - Callers can override it per request with `timeoutMs` (quick metadata calls)
+// - For streamed requests it is the idle timeout instead (no bytes from the server, or
+//   no chunk acknowledged by the frontend), so large downloads are never cut off
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use tauri::ipc::{Channel, JavaScriptChannelId};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Semaphore;
+
+use crate::app_config;
+use crate::http_clients::{self, ClientPurpose};
+use crate::http_policy;
+use crate::logger;
+
+// Backpressure: each chunk consumes one credit and the frontend grants
+// credits with ack_http_chunks as it consumes them. The body is not read from the
+// socket while no credit is available, so a slow consumer throttles the sidecar through
+// TCP flow control instead of queueing the whole body in the IPC channel. Nothing is
+// sent before the first credit.
+
+// Retries (buffered requests): connection failures and 502/503/504 are retried with
+// exponential backoff, by default only for idempotent methods. Connection refused is
+// typical while a sidecar restarts; timeouts are not retried since every attempt
+// already waited the full timeout. Failures are returned as HttpProxyError.
+
+/// Largest payload carried by a single `http:{id}:chunk` (before base64)
+const STREAM_CHUNK_MAX_BYTES: usize = 256 * 1024;
+/// Upper bound for credits granted in one ack
+const STREAM_MAX_CREDITS_PER_ACK: u32 = 64;
+
+/// Retries for requests that do not set `retries`
+const DEFAULT_RETRIES: u32 = 2;
+/// Upper bound for `retries`
+const MAX_RETRIES: u32 = 5;
+/// Backoff before the first retry, doubled for each further one
+const RETRY_BACKOFF_BASE_MS: u64 = 250;
+const RETRY_BACKOFF_MAX_MS: u64 = 2_000;
+/// Gateway statuses worth another attempt
+const RETRYABLE_STATUSES: [u16; 3] = [502, 503, 504];
+
+/// Generic HTTP request proxy - bypasses WebView CORS entirely
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpRequest {
+    pub url: String,
+    /// Any HTTP method (GET, PATCH, HEAD, OPTIONS, ...)
+    pub method: String,
+    pub body: Option<String>,
+    /// How `body` is encoded; base64 carries raw bytes
+    #[serde(default)]
+    pub body_encoding: BodyEncoding,
+    pub headers: Option<std::collections::HashMap<String, String>>,
+    /// multipart/form-data parts (instead of `body`)
+    pub multipart: Option<Vec<MultipartPart>>,
+    /// Total timeout per attempt (default: runtimeSettings.httpProxyTimeoutSecs).
+    /// proxy_http_request only; streamed requests use the idle timeout.
+    pub timeout_ms: Option<u64>,
+    /// Extra attempts after a retryable failure (default 2, max 5)
+    pub retries: Option<u32>,
+    /// Which methods may be retried
+    #[serde(default)]
+    pub retry_on: RetryOn,
+}
+
+/// Retry condition for proxy_http_request
+#[derive(serde::Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum RetryOn {
+    /// GET, HEAD, OPTIONS, TRACE, PUT and DELETE only
+    #[default]
+    Idempotent,
+    /// Any method (the caller knows the endpoint is safe to repeat)
+    Always,
+    Never,
+}
+
+impl RetryOn {
+    fn allows(self, method: &reqwest::Method) -> bool {
+        match self {
+            RetryOn::Idempotent => method.is_idempotent(),
+            RetryOn::Always => true,
+            RetryOn::Never => false,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BodyEncoding {
+    /// UTF-8 text, sent as-is
+    #[default]
+    Text,
+    /// Base64 of the raw bytes to send
+    Base64,
+}
+
+/// One field of a multipart/form-data upload
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MultipartPart {
+    pub name: String,
+    /// Text value of a plain field
+    pub value: Option<String>,
+    /// Base64 file content (takes precedence over `value`)
+    pub data: Option<String>,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+}
+
+/// Request body ready to send
+#[derive(Debug)]
+struct EncodedBody {
+    bytes: Vec<u8>,
+    /// Used when the caller did not set Content-Type
+    content_type: String,
+    /// Content-Type must win over the caller's (multipart boundary)
+    forced: bool,
+}
+
+#[derive(serde::Serialize)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+    pub headers: std::collections::HashMap<String, String>,
+    /// True if body is base64 encoded (for binary responses)
+    pub is_base64: bool,
+}
+
+/// What went wrong in a failed proxy_http_request
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum HttpErrorKind {
+    /// Invalid request (method, body encoding) or client setup failure
+    Request,
+    /// Could not connect (e.g. connection refused while the sidecar restarts)
+    Connect,
+    /// The attempt exceeded its timeout
+    Timeout,
+    /// A retryable status (502/503/504) persisted through all retries
+    Status,
+    /// The response body could not be read
+    Body,
+    /// Aborted by cancel_http_request or because the sidecar stopped
+    Cancelled,
+    /// The URL (or a redirect hop) is not a running sidecar or an allowlisted origin
+    /// (see http_policy.rs)
+    Forbidden,
+}
+
+/// Error returned by proxy_http_request
+#[derive(serde::Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpProxyError {
+    pub kind: HttpErrorKind,
+    pub message: String,
+    /// Attempts made, including the failed one (0 if cancelled)
+    pub attempts: u32,
+    /// Last response status (kind `status`)
+    pub status: Option<u16>,
+}
+
+impl HttpProxyError {
+    fn new(kind: HttpErrorKind, message: impl Into<String>, attempts: u32) -> Self {
+        Self { kind, message: message.into(), attempts, status: None }
+    }
+
+    /// Classify a reqwest send error
+    fn from_send(e: &reqwest::Error, attempts: u32) -> Self {
+        let kind = if e.is_timeout() {
+            HttpErrorKind::Timeout
+        } else if e.is_connect() {
+            HttpErrorKind::Connect
+        } else if e.is_body() || e.is_decode() {
+            HttpErrorKind::Body
+        } else if e.is_redirect() {
+            HttpErrorKind::Forbidden
+        } else {
+            HttpErrorKind::Request
+        };
+        let message = match e.source() {
+            Some(source) => format!("{} ({})", e, source),
+            None => e.to_string(),
+        };
+        Self::new(kind, message, attempts)
+    }
+
+    fn is_retryable(&self) -> bool {
+        self.kind == HttpErrorKind::Connect
+    }
+}
+
+impl std::fmt::Display for HttpProxyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} error after {} attempt(s): {}", self.kind, self.attempts, self.message)
+    }
+}
+
+/// Payload of `http:{id}:head`
+#[derive(Clone, serde::Serialize)]
+struct HttpStreamHead {
+    status: u16,
+    headers: HashMap<String, String>,
+}
+
+/// Payload of `http:{id}:chunk`
+#[derive(Clone, serde::Serialize)]
+struct HttpStreamChunk {
+    /// 0-based chunk index
+    seq: u64,
+    /// Base64-encoded body bytes
+    data: String,
+}
+
+/// Payload of `http:{id}:end`
+#[derive(Clone, serde::Serialize)]
+struct HttpStreamEnd {
+    bytes: u64,
+    chunks: u64,
+}
+
+/// Payload of `http:{id}:error`
+#[derive(Clone, serde::Serialize)]
+struct HttpStreamError {
+    message: String,
+    /// True when the request was aborted by cancel_http_request
+    cancelled: bool,
+}
+
+/// Message pushed over a streamed request's channel, in order:
+/// `head`, any number of `chunk`, then `end` or `error`; each variant mirrors one event
+#[derive(Clone, serde::Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum HttpStreamMessage {
+    Head(HttpStreamHead),
+    Chunk(HttpStreamChunk),
+    End(HttpStreamEnd),
+    Error(HttpStreamError),
+}
+
+impl HttpStreamMessage {
+    /// Name of the equivalent `http:{id}:*` event
+    fn event_name(&self, request_id: &str) -> String {
+        let suffix = match self {
+            HttpStreamMessage::Head(_) => "head",
+            HttpStreamMessage::Chunk(_) => "chunk",
+            HttpStreamMessage::End(_) => "end",
+            HttpStreamMessage::Error(_) => "error",
+        };
+        format!("http:{}:{}", request_id, suffix)
+    }
+}
+
+/// Where a streamed request's body is delivered
+#[derive(Clone)]
+enum HttpStreamTarget {
+    /// App-wide `http:{id}:*` events
+    Events(AppHandle),
+    /// Ordered messages to the webview that started the request
+    Channel(Channel<HttpStreamMessage>),
+}
+
+impl HttpStreamTarget {
+    /// Fails once the receiving webview is gone (channel) or the emit failed
+    fn send(&self, request_id: &str, message: HttpStreamMessage) -> Result<(), String> {
+        match self {
+            HttpStreamTarget::Channel(channel) => channel.send(message).map_err(|e| e.to_string()),
+            HttpStreamTarget::Events(app) => {
+                let name = message.event_name(request_id);
+                match message {
+                    HttpStreamMessage::Head(payload) => app.emit(&name, payload),
+                    HttpStreamMessage::Chunk(payload) => app.emit(&name, payload),
+                    HttpStreamMessage::End(payload) => app.emit(&name, payload),
+                    HttpStreamMessage::Error(payload) => app.emit(&name, payload),
+                }
+                .map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+/// Delivery side of a streamed request
+struct HttpStreamSink {
+    /// Chunk credits granted by ack_http_chunks
+    credits: Arc<Semaphore>,
+    target: HttpStreamTarget,
+}
+
+/// A proxied request that is still running
+struct InFlightRequest {
+    abort: tokio::task::AbortHandle,
+    /// Streamed requests only
+    stream: Option<HttpStreamSink>,
+    /// Target port, used to cancel a stopped sidecar's requests
+    port: Option<u16>,
+}
+
+/// State for in-flight HTTP requests (request id -> in-flight request)
+#[derive(Default)]
+pub struct HttpProxyState {
+    requests: Mutex<HashMap<String, InFlightRequest>>,
+}
+
+impl HttpProxyState {
+    fn remove(&self, request_id: &str) -> Option<InFlightRequest> {
+        self.requests.lock().ok()?.remove(request_id)
+    }
+
+    /// Spawn `task` and register it under `request_id`
+    fn spawn<T: Send + 'static>(
+        &self,
+        request_id: &str,
+        url: &str,
+        stream: Option<HttpStreamSink>,
+        task: impl std::future::Future<Output = T> + Send + 'static,
+    ) -> Result<tokio::task::JoinHandle<T>, String> {
+        let mut requests = self.requests.lock().map_err(|e| e.to_string())?;
+        if requests.contains_key(request_id) {
+            return Err(format!("[proxy] Request id {} is already in use", request_id));
+        }
+        let handle = tokio::spawn(task);
+        let port = reqwest::Url::parse(url).ok().and_then(|u| u.port_or_known_default());
+        requests.insert(request_id.to_string(), InFlightRequest { abort: handle.abort_handle(), stream, port });
+        Ok(handle)
+    }
+
+    /// Unregister and return every request targeting `port`
+    fn take_for_port(&self, port: u16) -> Vec<(String, InFlightRequest)> {
+        let Ok(mut requests) = self.requests.lock() else {
+            return Vec::new();
+        };
+        let ids: Vec<String> = requests.iter()
+            .filter(|(_, request)| request.port == Some(port))
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.into_iter().filter_map(|id| requests.remove(&id).map(|r| (id, r))).collect()
+    }
+}
+
+/// Check if content type indicates binary data
+fn is_binary_content_type(content_type: &str) -> bool {
+    let ct = content_type.to_lowercase();
+    ct.starts_with("image/") ||
+    ct.starts_with("audio/") ||
+    ct.starts_with("video/") ||
+    ct.starts_with("application/octet-stream") ||
+    ct.starts_with("application/pdf")
+}
+
+/// Quote a multipart name/filename the way browsers do (HTML form encoding)
+fn escape_multipart_param(value: &str) -> String {
+    value.replace('"', "%22").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Encode parts as multipart/form-data (RFC 7578)
+fn encode_multipart(parts: &[MultipartPart], boundary: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    for part in parts {
+        out.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        let mut disposition = format!("Content-Disposition: form-data; name=\"{}\"", escape_multipart_param(&part.name));
+        if let Some(filename) = &part.filename {
+            disposition.push_str(&format!("; filename=\"{}\"", escape_multipart_param(filename)));
+        }
+        out.extend_from_slice(disposition.as_bytes());
+        out.extend_from_slice(b"\r\n");
+        let content_type = match (&part.content_type, &part.filename) {
+            (Some(content_type), _) => Some(content_type.as_str()),
+            (None, Some(_)) => Some("application/octet-stream"),
+            (None, None) => None,
+        };
+        if let Some(content_type) = content_type {
+            out.extend_from_slice(format!("Content-Type: {}\r\n", content_type).as_bytes());
+        }
+        out.extend_from_slice(b"\r\n");
+        match (&part.data, &part.value) {
+            (Some(data), _) => out.extend_from_slice(
+                &BASE64.decode(data)
+                    .map_err(|e| format!("[proxy] Invalid base64 in multipart field {}: {}", part.name, e))?,
+            ),
+            (None, Some(value)) => out.extend_from_slice(value.as_bytes()),
+            (None, None) => {}
+        }
+        out.extend_from_slice(b"\r\n");
+    }
+    out.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    Ok(out)
+}
+
+/// Turn `body` / `multipart` into bytes plus a default Content-Type
+fn encode_body(request: &HttpRequest) -> Result<Option<EncodedBody>, String> {
+    match (&request.body, &request.multipart) {
+        (Some(_), Some(_)) => Err("[proxy] body and multipart are mutually exclusive".to_string()),
+        (None, Some(parts)) => {
+            let boundary = format!("----MyAgentsFormBoundary{}", uuid::Uuid::new_v4().simple());
+            Ok(Some(EncodedBody {
+                bytes: encode_multipart(parts, &boundary)?,
+                content_type: format!("multipart/form-data; boundary={}", boundary),
+                forced: true,
+            }))
+        }
+        (Some(body), None) => Ok(Some(match request.body_encoding {
+            BodyEncoding::Text => EncodedBody {
+                bytes: body.clone().into_bytes(),
+                content_type: "application/json".to_string(),
+                forced: false,
+            },
+            BodyEncoding::Base64 => EncodedBody {
+                bytes: BASE64.decode(body).map_err(|e| format!("[proxy] Invalid base64 body: {}", e))?,
+                content_type: "application/octet-stream".to_string(),
+                forced: false,
+            },
+        })),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Translate the frontend request into a reqwest builder
+fn build_request(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    request: &HttpRequest,
+) -> Result<reqwest::RequestBuilder, String> {
+    let fail = |err: String| {
+        logger::error(app, &err);
+        err
+    };
+    let method = reqwest::Method::from_bytes(request.method.to_uppercase().as_bytes())
+        .map_err(|_| fail(format!("[proxy] Unsupported method: {}", request.method)))?;
+    let body = encode_body(request).map_err(fail)?;
+
+    let mut req_builder = client.request(method, &request.url);
+    let mut has_content_type = false;
+
+    // Add headers (a multipart body brings its own Content-Type with the boundary)
+    if let Some(headers) = &request.headers {
+        for (key, value) in headers {
+            if key.eq_ignore_ascii_case("content-type") {
+                if body.as_ref().is_some_and(|b| b.forced) {
+                    continue;
+                }
+                has_content_type = true;
+            }
+            req_builder = req_builder.header(key, value);
+        }
+    }
+
+    if let Some(body) = body {
+        logger::debug(app, format!("[proxy] Request body length: {} bytes", body.bytes.len()));
+        // Default only: the caller's Content-Type is respected
+        if !has_content_type {
+            req_builder = req_builder.header("Content-Type", body.content_type);
+        }
+        req_builder = req_builder.body(body.bytes);
+    }
+
+    Ok(req_builder)
+}
+
+/// Log a failed send with as much detail as reqwest exposes
+fn log_send_error(app: &AppHandle, e: &reqwest::Error) {
+    let mut err = format!("[proxy] Request failed: {}", e);
+
+    // Add detailed error information for debugging
+    if e.is_connect() {
+        err.push_str(" (Connection error - cannot establish connection)");
+    }
+    if e.is_timeout() {
+        err.push_str(" (Timeout error - request took too long)");
+    }
+    if e.is_request() {
+        err.push_str(" (Request error - invalid request)");
+    }
+    if e.is_body() {
+        err.push_str(" (Body error - failed to read response body)");
+    }
+
+    // Try to get the source error
+    if let Some(source) = e.source() {
+        err.push_str(&format!(" | Source: {}", source));
+    }
+
+    logger::error(app, &err);
+}
+
+/// Collect response headers that are valid strings
+fn response_headers(response: &reqwest::Response) -> HashMap<String, String> {
+    let mut resp_headers = HashMap::new();
+    for (key, value) in response.headers() {
+        if let Ok(v) = value.to_str() {
+            resp_headers.insert(key.to_string(), v.to_string());
+        }
+    }
+    resp_headers
+}
+
+/// Proxy an HTTP request through Rust - completely bypasses WebView CORS
+///
+/// `request_id` makes the request cancellable with cancel_http_request
+#[tauri::command]
+pub async fn proxy_http_request(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<HttpProxyState>>,
+    request: HttpRequest,
+    request_id: Option<String>,
+) -> Result<HttpResponse, HttpProxyError> {
+    let target = http_policy::authorize(&app, &request.url)
+        .map_err(|e| HttpProxyError::new(HttpErrorKind::Forbidden, e, 0))?;
+    let request_id = request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let url = request.url.clone();
+    let handle = state
+        .spawn(&request_id, &url, None, send_with_retries(app.clone(), request, target.client_purpose()))
+        .map_err(|e| HttpProxyError::new(HttpErrorKind::Request, e, 0))?;
+    let result = handle.await;
+    state.remove(&request_id);
+    match result {
+        Ok(response) => response,
+        Err(e) if e.is_cancelled() => {
+            logger::info(&app, format!("[proxy] {} - Request {} cancelled", url, request_id));
+            Err(HttpProxyError::new(HttpErrorKind::Cancelled, format!("Request {} cancelled", request_id), 0))
+        }
+        Err(e) => Err(HttpProxyError::new(
+            HttpErrorKind::Request,
+            format!("Request {} failed: {}", request_id, e),
+            0,
+        )),
+    }
+}
+
+/// Delay before retry number `retry` (1-based)
+fn retry_backoff(retry: u32) -> Duration {
+    let factor = 1u64 << retry.saturating_sub(1).min(16);
+    Duration::from_millis((RETRY_BACKOFF_BASE_MS * factor).min(RETRY_BACKOFF_MAX_MS))
+}
+
+/// Send a buffered request, retrying connection failures and gateway statuses
+async fn send_with_retries(
+    app: AppHandle,
+    request: HttpRequest,
+    purpose: ClientPurpose,
+) -> Result<HttpResponse, HttpProxyError> {
+    let request_error = |e: String| HttpProxyError::new(HttpErrorKind::Request, e, 0);
+    logger::info(&app, format!("[proxy] {} {} - Starting", request.method, request.url));
+
+    // Shared pooled client; total timeout per attempt
+    let client = http_clients::client(&app, purpose).map_err(request_error)?;
+    http_clients::prepare_request(purpose, &request.url).await;
+    let timeout = match request.timeout_ms {
+        Some(ms) => Duration::from_millis(ms.max(1)),
+        None => Duration::from_secs(app_config::runtime_settings().http_proxy_timeout_secs),
+    };
+    let req_builder = build_request(&app, &client, &request).map_err(request_error)?.timeout(timeout);
+    // Already validated by build_request
+    let method = reqwest::Method::from_bytes(request.method.to_uppercase().as_bytes())
+        .unwrap_or(reqwest::Method::POST);
+    let max_attempts = if request.retry_on.allows(&method) {
+        request.retries.unwrap_or(DEFAULT_RETRIES).min(MAX_RETRIES) + 1
+    } else {
+        1
+    };
+
+    let mut attempt = 1;
+    loop {
+        let builder = req_builder
+            .try_clone()
+            .ok_or_else(|| request_error("[proxy] Request body cannot be cloned".to_string()))?;
+        let result = send_buffered(&app, &request, purpose, builder, attempt).await;
+
+        let retryable = match &result {
+            Ok(response) => RETRYABLE_STATUSES.contains(&response.status),
+            Err(e) => e.is_retryable(),
+        };
+        if !retryable || attempt >= max_attempts {
+            return match result {
+                // Retried and still failing: report it as an error, not a normal response
+                Ok(response) if retryable && attempt > 1 => Err(HttpProxyError {
+                    status: Some(response.status),
+                    ..HttpProxyError::new(
+                        HttpErrorKind::Status,
+                        format!("HTTP {} after {} attempts", response.status, attempt),
+                        attempt,
+                    )
+                }),
+                other => other,
+            };
+        }
+
+        let delay = retry_backoff(attempt);
+        let reason = match &result {
+            Ok(response) => format!("HTTP {}", response.status),
+            Err(e) => e.message.clone(),
+        };
+        logger::warn(&app, format!(
+            "[proxy] {} {} - Attempt {}/{} failed ({}), retrying in {}ms",
+            request.method, request.url, attempt, max_attempts, reason, delay.as_millis()
+        ));
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Send one attempt and buffer the whole response
+async fn send_buffered(
+    app: &AppHandle,
+    request: &HttpRequest,
+    purpose: ClientPurpose,
+    req_builder: reqwest::RequestBuilder,
+    attempt: u32,
+) -> Result<HttpResponse, HttpProxyError> {
+    logger::info(app, format!("[proxy] {} {} - Sending request...", request.method, request.url));
+
+    // Send request with detailed error logging
+    let response = http_clients::send(app, purpose, req_builder).await.map_err(|e| {
+        log_send_error(app, &e);
+        HttpProxyError::from_send(&e, attempt)
+    })?;
+
+    let status = response.status().as_u16();
+
+    // Collect response headers
+    let resp_headers = response_headers(&response);
+
+    // Check if this is binary content
+    let content_type = resp_headers.get("content-type")
+        .map(|s| s.as_str())
+        .unwrap_or("");
+
+    let is_binary = is_binary_content_type(content_type);
+
+    // Get response body - encode as base64 if binary
+    let (body, is_base64) = if is_binary {
+        let bytes = response.bytes().await.map_err(|e| {
+            let err = format!("[proxy] Failed to read binary body: {}", e);
+            logger::error(app, &err);
+            HttpProxyError::new(HttpErrorKind::Body, e.to_string(), attempt)
+        })?;
+        logger::debug(app, format!("[proxy] Binary response: {} bytes", bytes.len()));
+        (BASE64.encode(&bytes), true)
+    } else {
+        let text = response.text().await.map_err(|e| {
+            let err = format!("[proxy] Failed to read text body: {}", e);
+            logger::error(app, &err);
+            HttpProxyError::new(HttpErrorKind::Body, e.to_string(), attempt)
+        })?;
+        logger::debug(app, format!("[proxy] Text response: {} bytes", text.len()));
+        (text, false)
+    };
+
+    logger::info(app, format!("[proxy] {} {} - Complete (status: {}, body: {} bytes)",
+        request.method, request.url, status, body.len()));
+
+    Ok(HttpResponse {
+        status,
+        body,
+        headers: resp_headers,
+        is_base64,
+    })
+}
+
+/// Proxy an HTTP request and stream the response body as events
+///
+/// Returns the request id right away. `request_id` lets the caller subscribe to
+/// `http:{id}:*` before invoking, and `initial_credits` grants the first chunks up
+/// front (otherwise nothing is emitted until ack_http_chunks is called).
+/// With `channel`, the body goes only to the calling webview instead.
+#[tauri::command]
+pub async fn proxy_http_request_stream(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<'_, Arc<HttpProxyState>>,
+    request: HttpRequest,
+    request_id: Option<String>,
+    initial_credits: Option<u32>,
+    channel: Option<JavaScriptChannelId>,
+) -> Result<String, HttpProxyError> {
+    // Option<Channel> is not a command argument; resolve the id against the caller
+    let output = match channel {
+        Some(id) => HttpStreamTarget::Channel(id.channel_on(webview)),
+        None => HttpStreamTarget::Events(app.clone()),
+    };
+    let request_error = |e: String| HttpProxyError::new(HttpErrorKind::Request, e, 0);
+    let target = http_policy::authorize(&app, &request.url)
+        .map_err(|e| HttpProxyError::new(HttpErrorKind::Forbidden, e, 0))?;
+    let request_id = request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    logger::info(&app, format!(
+        "[proxy] {} {} - Starting stream {}",
+        request.method, request.url, request_id
+    ));
+
+    // No total timeout: the client's idle timeout applies to streamed bodies
+    let purpose = target.client_purpose();
+    let client = http_clients::client(&app, purpose).map_err(request_error)?;
+    http_clients::prepare_request(purpose, &request.url).await;
+    let req_builder = build_request(&app, &client, &request).map_err(request_error)?;
+    let credits = Arc::new(Semaphore::new(
+        initial_credits.unwrap_or(0).min(STREAM_MAX_CREDITS_PER_ACK) as usize,
+    ));
+
+    let task_state = Arc::clone(&state);
+    let task_id = request_id.clone();
+    let task_credits = Arc::clone(&credits);
+    let task_output = output.clone();
+    let sink = HttpStreamSink { credits, target: output };
+    state.spawn(&request_id, &request.url, Some(sink), async move {
+        stream_response(&app, &task_id, purpose, req_builder, &task_credits, &task_output).await;
+        task_state.remove(&task_id);
+    }).map_err(request_error)?;
+    Ok(request_id)
+}
+
+/// Grant `count` more chunks to a streamed request
+#[tauri::command]
+pub fn ack_http_chunks(
+    state: tauri::State<'_, Arc<HttpProxyState>>,
+    request_id: String,
+    count: u32,
+) -> Result<(), String> {
+    let requests = state.requests.lock().map_err(|e| e.to_string())?;
+    // Already finished or cancelled: late acks are harmless
+    if let Some(stream) = requests.get(&request_id).and_then(|r| r.stream.as_ref()) {
+        stream.credits.add_permits(count.min(STREAM_MAX_CREDITS_PER_ACK) as usize);
+    }
+    Ok(())
+}
+
+/// Abort an in-flight request; returns false if it had already finished
+#[tauri::command]
+pub fn cancel_http_request(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<HttpProxyState>>,
+    request_id: String,
+) -> Result<bool, String> {
+    let Some(request) = state.remove(&request_id) else {
+        return Ok(false);
+    };
+    abort_request(&app, &request_id, request);
+    Ok(true)
+}
+
+/// Cancel every in-flight request to `port` (its sidecar was stopped)
+pub fn cancel_requests_for_port(app: &AppHandle, state: &HttpProxyState, port: u16) -> usize {
+    let cancelled = state.take_for_port(port);
+    let count = cancelled.len();
+    for (request_id, request) in cancelled {
+        abort_request(app, &request_id, request);
+    }
+    if count > 0 {
+        logger::info(app, format!("[proxy] Cancelled {} request(s) to stopped sidecar on port {}", count, port));
+    }
+    count
+}
+
+/// Abort the task; streamed requests also get a final `http:{id}:error`
+fn abort_request(app: &AppHandle, request_id: &str, request: InFlightRequest) {
+    request.abort.abort();
+    logger::info(app, format!("[proxy] Request {} cancelled", request_id));
+    if let Some(stream) = request.stream {
+        let _ = stream.target.send(request_id, HttpStreamMessage::Error(HttpStreamError {
+            message: "Request cancelled".to_string(),
+            cancelled: true,
+        }));
+    }
+}
+
+/// Wait for one credit; errors if the frontend stops acknowledging chunks
+async fn take_credit(credits: &Semaphore, timeout: Duration) -> Result<(), String> {
+    match tokio::time::timeout(timeout, credits.acquire()).await {
+        Ok(Ok(permit)) => {
+            permit.forget();
+            Ok(())
+        }
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("No chunk acknowledged for {}s", timeout.as_secs())),
+    }
+}
+
+/// Body of a streamed request: send, then deliver head / chunks / end (or error)
+/// A closed channel (webview gone) stops the request.
+async fn stream_response(
+    app: &AppHandle,
+    request_id: &str,
+    purpose: ClientPurpose,
+    req_builder: reqwest::RequestBuilder,
+    credits: &Semaphore,
+    target: &HttpStreamTarget,
+) {
+    use futures_util::StreamExt;
+
+    let idle_timeout = Duration::from_secs(app_config::runtime_settings().http_proxy_timeout_secs);
+    let fail = |message: String| {
+        logger::error(app, format!("[proxy] Stream {} failed: {}", request_id, message));
+        let _ = target.send(request_id, HttpStreamMessage::Error(HttpStreamError { message, cancelled: false }));
+    };
+
+    let response = http_clients::send(app, purpose, req_builder).await;
+
+    // Nothing goes out until the frontend has subscribed and granted a credit
+    match tokio::time::timeout(idle_timeout, credits.acquire()).await {
+        Ok(Ok(_permit)) => {}
+        _ => {
+            logger::warn(app, format!("[proxy] Stream {} was never acknowledged, dropping", request_id));
+            return;
+        }
+    }
+
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            log_send_error(app, &e);
+            return fail(e.to_string());
+        }
+    };
+
+    let head = HttpStreamHead { status: response.status().as_u16(), headers: response_headers(&response) };
+    if let Err(e) = target.send(request_id, HttpStreamMessage::Head(head)) {
+        logger::warn(app, format!("[proxy] Stream {} dropped, delivery failed: {}", request_id, e));
+        return;
+    }
+
+    let mut stream = response.bytes_stream();
+    let mut seq: u64 = 0;
+    let mut total: u64 = 0;
+    while let Some(next) = stream.next().await {
+        let bytes = match next {
+            Ok(bytes) => bytes,
+            Err(e) => return fail(format!("Failed to read body after {} bytes: {}", total, e)),
+        };
+        for piece in bytes.chunks(STREAM_CHUNK_MAX_BYTES) {
+            if let Err(e) = take_credit(credits, idle_timeout).await {
+                return fail(e);
+            }
+            let chunk = HttpStreamChunk { seq, data: BASE64.encode(piece) };
+            if let Err(e) = target.send(request_id, HttpStreamMessage::Chunk(chunk)) {
+                logger::warn(app, format!("[proxy] Stream {} dropped, delivery failed: {}", request_id, e));
+                return;
+            }
+            seq += 1;
+            total += piece.len() as u64;
+        }
+    }
+
+    logger::info(app, format!(
+        "[proxy] Stream {} complete: {} bytes in {} chunks",
+        request_id, total, seq
+    ));
+    let _ = target.send(request_id, HttpStreamMessage::End(HttpStreamEnd { bytes: total, chunks: seq }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_credit_waits_for_ack() {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        runtime.block_on(async {
+            let credits = Arc::new(Semaphore::new(1));
+            assert!(take_credit(&credits, Duration::from_millis(50)).await.is_ok());
+            // Consumed credits are not returned
+            assert!(take_credit(&credits, Duration::from_millis(20)).await.is_err());
+
+            let acker = Arc::clone(&credits);
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                acker.add_permits(1);
+            });
+            assert!(take_credit(&credits, Duration::from_secs(5)).await.is_ok());
+        });
+    }
+
+    #[test]
+    fn test_requests_are_cancelled_by_port() {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        runtime.block_on(async {
+            let state = HttpProxyState::default();
+            let pending = || std::future::pending::<()>();
+            let a = state.spawn("a", "http://127.0.0.1:31415/api/x", None, pending()).unwrap();
+            let _b = state.spawn("b", "http://127.0.0.1:31416/api/x", None, pending()).unwrap();
+            assert!(state.spawn("a", "http://127.0.0.1:31415/", None, pending()).is_err());
+
+            let taken = state.take_for_port(31415);
+            assert_eq!(taken.len(), 1);
+            assert_eq!(taken[0].0, "a");
+            taken[0].1.abort.abort();
+            assert!(a.await.unwrap_err().is_cancelled());
+
+            assert!(state.take_for_port(31415).is_empty());
+            assert!(state.remove("b").is_some());
+        });
+    }
+
+    #[test]
+    fn test_stream_messages_mirror_events() {
+        let head = HttpStreamMessage::Head(HttpStreamHead {
+            status: 200,
+            headers: HashMap::from([("content-type".to_string(), "image/png".to_string())]),
+        });
+        assert_eq!(
+            serde_json::to_value(&head).unwrap(),
+            serde_json::json!({ "type": "head", "status": 200, "headers": { "content-type": "image/png" } })
+        );
+        assert_eq!(head.event_name("r1"), "http:r1:head");
+        let chunk = HttpStreamMessage::Chunk(HttpStreamChunk { seq: 3, data: "AAE=".into() });
+        assert_eq!(chunk.event_name("r1"), "http:r1:chunk");
+        assert_eq!(serde_json::to_value(&chunk).unwrap(), serde_json::json!({ "type": "chunk", "seq": 3, "data": "AAE=" }));
+        let end = HttpStreamMessage::End(HttpStreamEnd { bytes: 2, chunks: 1 });
+        assert_eq!(end.event_name("r1"), "http:r1:end");
+        assert_eq!(serde_json::to_value(&end).unwrap(), serde_json::json!({ "type": "end", "bytes": 2, "chunks": 1 }));
+        let error = HttpStreamMessage::Error(HttpStreamError { message: "Request cancelled".into(), cancelled: true });
+        assert_eq!(error.event_name("r1"), "http:r1:error");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "type": "error", "message": "Request cancelled", "cancelled": true })
+        );
+    }
+
+    fn request(body: Option<&str>, body_encoding: BodyEncoding, multipart: Option<Vec<MultipartPart>>) -> HttpRequest {
+        HttpRequest {
+            url: "http://127.0.0.1:31415/api/skill/upload".to_string(),
+            method: "POST".to_string(),
+            body: body.map(str::to_string),
+            body_encoding,
+            headers: None,
+            multipart,
+            timeout_ms: None,
+            retries: None,
+            retry_on: RetryOn::default(),
+        }
+    }
+
+    #[test]
+    fn test_encode_text_and_base64_bodies() {
+        let text = encode_body(&request(Some("{}"), BodyEncoding::Text, None)).unwrap().unwrap();
+        assert_eq!(text.bytes, b"{}");
+        assert_eq!(text.content_type, "application/json");
+        assert!(!text.forced);
+
+        let binary = encode_body(&request(Some("AP8Q"), BodyEncoding::Base64, None)).unwrap().unwrap();
+        assert_eq!(binary.bytes, vec![0x00, 0xFF, 0x10]);
+        assert_eq!(binary.content_type, "application/octet-stream");
+
+        assert!(encode_body(&request(Some("not base64!"), BodyEncoding::Base64, None)).is_err());
+        assert!(encode_body(&request(None, BodyEncoding::Text, None)).unwrap().is_none());
+        assert!(encode_body(&request(Some("{}"), BodyEncoding::Text, Some(vec![]))).is_err());
+    }
+
+    #[test]
+    fn test_encode_multipart() {
+        let parts = vec![
+            MultipartPart {
+                name: "scope".to_string(),
+                value: Some("user".to_string()),
+                data: None,
+                filename: None,
+                content_type: None,
+            },
+            MultipartPart {
+                name: "file".to_string(),
+                value: None,
+                data: Some(BASE64.encode(b"PK\x03\x04")),
+                filename: Some("my \"skill\".zip".to_string()),
+                content_type: None,
+            },
+        ];
+        let body = encode_multipart(&parts, "XYZ").unwrap();
+        let mut expected = b"--XYZ\r\nContent-Disposition: form-data; name=\"scope\"\r\n\r\nuser\r\n".to_vec();
+        expected.extend_from_slice(b"--XYZ\r\nContent-Disposition: form-data; name=\"file\"; filename=\"my %22skill%22.zip\"\r\n");
+        expected.extend_from_slice(b"Content-Type: application/octet-stream\r\n\r\nPK\x03\x04\r\n--XYZ--\r\n");
+        assert_eq!(body, expected);
+
+        let encoded = encode_body(&request(None, BodyEncoding::Text, Some(parts))).unwrap().unwrap();
+        assert!(encoded.forced);
+        assert!(encoded.content_type.starts_with("multipart/form-data; boundary=----MyAgentsFormBoundary"));
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        assert!(RetryOn::Idempotent.allows(&reqwest::Method::GET));
+        assert!(RetryOn::Idempotent.allows(&reqwest::Method::DELETE));
+        assert!(!RetryOn::Idempotent.allows(&reqwest::Method::POST));
+        assert!(RetryOn::Always.allows(&reqwest::Method::PATCH));
+        assert!(!RetryOn::Never.allows(&reqwest::Method::GET));
+
+        assert_eq!(retry_backoff(1), Duration::from_millis(250));
+        assert_eq!(retry_backoff(2), Duration::from_millis(500));
+        assert_eq!(retry_backoff(10), Duration::from_millis(RETRY_BACKOFF_MAX_MS));
+
+        let parsed: HttpRequest = serde_json::from_str(
+            r#"{"url":"http://127.0.0.1:1/","method":"GET","timeoutMs":500,"retries":1,"retryOn":"always"}"#,
+        ).unwrap();
+        assert_eq!((parsed.timeout_ms, parsed.retries, parsed.retry_on), (Some(500), Some(1), RetryOn::Always));
+    }
+
+    #[test]
+    fn test_send_errors_are_classified() {
+        // Bind then drop to get a port nobody listens on
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let silent = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let silent_port = silent.local_addr().unwrap().port();
+
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let client = reqwest::Client::builder().no_proxy().build().unwrap();
+            let refused = client.get(format!("http://127.0.0.1:{}/api/skills", port)).send().await.unwrap_err();
+            let error = HttpProxyError::from_send(&refused, 2);
+            assert_eq!((error.kind, error.attempts), (HttpErrorKind::Connect, 2));
+            assert!(error.is_retryable());
+
+            let timed_out = client
+                .get(format!("http://127.0.0.1:{}/api/skills", silent_port))
+                .timeout(Duration::from_millis(50))
+                .send()
+                .await
+                .unwrap_err();
+            let error = HttpProxyError::from_send(&timed_out, 1);
+            assert_eq!(error.kind, HttpErrorKind::Timeout);
+            assert!(!error.is_retryable());
+        });
+        drop(silent);
+    }
+
+    #[test]
+    fn test_binary_content_types() {
+        assert!(is_binary_content_type("image/png"));
+        assert!(is_binary_content_type("Application/PDF"));
+        assert!(!is_binary_content_type("application/json; charset=utf-8"));
+    }
+}
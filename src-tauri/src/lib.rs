@@ -1,14 +1,27 @@
 // MyAgents Tauri Application
 // Main entry point with sidecar lifecycle management
 
+mod app_config;
 mod commands;
+mod http_clients;
+mod http_policy;
+mod http_proxy;
 pub mod logger;
+mod pac;
 mod proxy_config;
 mod sidecar;
+// Public only for benches/sse_decoder.rs
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod sse_decoder;
+#[cfg(not(feature = "bench"))]
+mod sse_decoder;
 mod sse_proxy;
+mod sse_recorder;
+mod tls_config;
 mod updater;
 
-use sidecar::{cleanup_stale_sidecars, create_sidecar_state, stop_all_sidecars};
+use sidecar::{cleanup_stale_sidecars, create_sidecar_state, spawn_sidecar_supervisor, stop_all_sidecars};
 use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
 
@@ -22,6 +35,9 @@ pub fn run() {
     let sidecar_state = create_sidecar_state();
     let sidecar_state_for_window = sidecar_state.clone();
     let sidecar_state_for_exit = sidecar_state.clone();
+    let sidecar_state_for_supervisor = sidecar_state.clone();
+    let sidecar_state_for_config = sidecar_state.clone();
+    let sidecar_state_for_sse = sidecar_state.clone();
 
     // Track if cleanup has been performed to avoid duplicate cleanup
     let cleanup_done = Arc::new(AtomicBool::new(false));
@@ -30,6 +46,12 @@ pub fn run() {
 
     // Create SSE proxy state
     let sse_proxy_state = Arc::new(sse_proxy::SseProxyState::default());
+    let sse_proxy_state_for_binding = sse_proxy_state.clone();
+    // Create HTTP proxy state (in-flight requests)
+    let http_proxy_state = Arc::new(http_proxy::HttpProxyState::default());
+    // Shared reqwest clients (rebuilt when network settings change)
+    let http_clients = Arc::new(http_clients::HttpClientRegistry::new());
+    let http_clients_for_config = http_clients.clone();
 
     // Build the app first, then run with event handler
     // This allows us to handle RunEvent::ExitRequested for Cmd+Q and Dock quit
@@ -41,6 +63,8 @@ pub fn run() {
         .plugin(tauri_plugin_process::init())
         .manage(sidecar_state)
         .manage(sse_proxy_state)
+        .manage(http_proxy_state)
+        .manage(http_clients)
         .invoke_handler(tauri::generate_handler![
             // Legacy commands (backward compatibility)
             commands::cmd_start_sidecar,
@@ -62,7 +86,13 @@ pub fn run() {
             sse_proxy::start_sse_proxy,
             sse_proxy::stop_sse_proxy,
             sse_proxy::stop_all_sse_proxies,
-            sse_proxy::proxy_http_request,
+            sse_recorder::replay_sse_recording,
+            // HTTP proxy commands
+            http_proxy::proxy_http_request,
+            http_proxy::proxy_http_request_stream,
+            http_proxy::ack_http_chunks,
+            http_proxy::cancel_http_request,
+            http_clients::get_http_client_metrics,
             // Updater commands
             updater::check_and_download_update,
             updater::restart_app,
@@ -70,8 +100,12 @@ pub fn run() {
             // Platform & device info
             commands::cmd_get_platform,
             commands::cmd_get_device_id,
+            // Proxy credentials
+            commands::cmd_set_proxy_password,
+            commands::cmd_has_proxy_password,
+            proxy_config::test_proxy_connectivity,
         ])
-        .setup(|app| {
+        .setup(move |app| {
             // Initialize logging for all builds
             // Debug builds: DEBUG level for verbose output including third-party crates
             // Production builds: INFO level for important events only
@@ -110,6 +144,26 @@ pub fn run() {
                 }
             }
 
+            // PAC proxy scripts are evaluated with the bundled Bun runtime
+            if let Some(bun_path) = sidecar::find_bun_executable(app.handle()) {
+                pac::set_bun_executable(bun_path);
+            }
+
+            // Watch sidecar processes and restart them if they crash
+            spawn_sidecar_supervisor(app.handle().clone(), sidecar_state_for_supervisor);
+
+            // Attach Tab SSE streams to their sidecars (and reattach after restarts)
+            sse_proxy::bind_to_sidecars(app.handle(), sse_proxy_state_for_binding, &sidecar_state_for_sse);
+
+            // Watch ~/.myagents/config.json and apply changes without restarting Tabs by hand
+            // (shared reqwest clients are rebuilt on next use; sidecars need a restart for
+            // new proxy env vars)
+            let config_app_handle = app.handle().clone();
+            app_config::spawn_config_watcher(app.handle().clone(), move |change| {
+                http_clients_for_config.apply_config_change(change);
+                sidecar::apply_config_change(&config_app_handle, &sidecar_state_for_config, change);
+            });
+
             // Start background update check (5 second delay to let app initialize)
             log::info!("[App] Setup complete, spawning background update check task...");
             let app_handle = app.handle().clone();
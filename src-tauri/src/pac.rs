@@ -0,0 +1,396 @@
+//! PAC (Proxy Auto-Config) evaluation for `proxySettings.mode = "pac"`
+//!
+//! PAC scripts are JavaScript, so they are evaluated with the bundled Bun runtime
+//! (the same binary that runs the sidecars). Each evaluation runs `FindProxyForURL`
+//! in a short-lived `bun -e` process with the standard PAC helper functions defined.
+//!
+//! - The PAC script itself is always fetched directly (never through a proxy)
+//! - DNS lookups for the destination are done in Rust and handed to the script
+//! - Results are cached per (PAC source, scheme, host) for `PAC_CACHE_TTL_SECS`
+//!
+//! Evaluation blocks (subprocess, DNS) for up to `PAC_EVAL_TIMEOUT_SECS`: call `find_proxy`
+//! from a blocking thread. reqwest's connector only reads the cache (`cached_proxy`);
+//! `proxy_config::prepare_external_request` fills it before each request.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::net::{IpAddr, ToSocketAddrs, UdpSocket};
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Maximum time a single PAC evaluation (including fetching the script) may take
+const PAC_EVAL_TIMEOUT_SECS: u64 = 10;
+/// How long a PAC result is reused for the same destination host
+const PAC_CACHE_TTL_SECS: u64 = 300;
+/// Output kept per evaluator stream (the result is written last, errors end stderr)
+const PAC_OUTPUT_MAX_BYTES: usize = 64 * 1024;
+
+/// Environment variables that must not leak into the evaluator (the PAC file is fetched directly)
+const PROXY_ENV_VARS: [&str; 6] = [
+    "HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY", "http_proxy", "https_proxy", "all_proxy",
+];
+
+/// PAC helper functions + evaluation, run via `bun -e`
+/// Date/time based helpers (dateRange, timeRange) are rarely used in practice and always match.
+const PAC_RUNTIME_JS: &str = r#"
+const env = process.env;
+const targetHost = env.MYAGENTS_PAC_HOST;
+const targetIp = env.MYAGENTS_PAC_HOST_IP || null;
+const ipToInt = (ip) => {
+  const parts = String(ip).split('.');
+  if (parts.length !== 4) return null;
+  let n = 0;
+  for (const part of parts) {
+    const v = Number(part);
+    if (!Number.isInteger(v) || v < 0 || v > 255) return null;
+    n = n * 256 + v;
+  }
+  return n;
+};
+const DAYS = ['SUN', 'MON', 'TUE', 'WED', 'THU', 'FRI', 'SAT'];
+Object.assign(globalThis, {
+  isPlainHostName: (h) => !h.includes('.'),
+  dnsDomainIs: (h, d) => h.toLowerCase().endsWith(d.toLowerCase()),
+  localHostOrDomainIs: (h, hd) => h === hd || (!h.includes('.') && hd.startsWith(h + '.')),
+  dnsResolve: (h) => (ipToInt(h) !== null ? h : h === targetHost ? targetIp : null),
+  isResolvable: (h) => globalThis.dnsResolve(h) !== null,
+  isInNet: (h, pattern, mask) => {
+    const ip = ipToInt(globalThis.dnsResolve(h));
+    const p = ipToInt(pattern);
+    const m = ipToInt(mask);
+    if (ip === null || p === null || m === null) return false;
+    return ((ip & m) >>> 0) === ((p & m) >>> 0);
+  },
+  myIpAddress: () => env.MYAGENTS_PAC_MY_IP || '127.0.0.1',
+  dnsDomainLevels: (h) => h.split('.').length - 1,
+  shExpMatch: (s, pattern) => new RegExp('^' + pattern
+    .replace(/[.+^${}()|[\]\\]/g, '\\$&')
+    .replace(/\*/g, '.*')
+    .replace(/\?/g, '.') + '$').test(s),
+  weekdayRange: (wd1, wd2, gmt) => {
+    if (wd2 === 'GMT') { gmt = wd2; wd2 = undefined; }
+    const now = new Date();
+    const today = gmt === 'GMT' ? now.getUTCDay() : now.getDay();
+    const from = DAYS.indexOf(wd1);
+    const to = wd2 === undefined ? from : DAYS.indexOf(wd2);
+    return from <= to ? today >= from && today <= to : today >= from || today <= to;
+  },
+  dateRange: () => true,
+  timeRange: () => true,
+  alert: () => {},
+});
+(async () => {
+  const source = env.MYAGENTS_PAC_SOURCE;
+  let script;
+  if (/^https?:\/\//i.test(source)) {
+    const res = await fetch(source);
+    if (!res.ok) throw new Error(`failed to fetch PAC file: HTTP ${res.status}`);
+    script = await res.text();
+  } else {
+    const fs = require('fs');
+    script = fs.readFileSync(/^file:/i.test(source) ? new URL(source) : source, 'utf8');
+  }
+  (0, eval)(script);
+  if (typeof globalThis.FindProxyForURL !== 'function') {
+    throw new Error('PAC file does not define FindProxyForURL');
+  }
+  const result = globalThis.FindProxyForURL(env.MYAGENTS_PAC_URL, targetHost);
+  process.stdout.write(String(result ?? 'DIRECT'));
+})().catch((e) => {
+  process.stderr.write(String(e && e.message ? e.message : e));
+  process.exit(1);
+});
+"#;
+
+/// One entry of a `FindProxyForURL` result such as `PROXY a:8080; SOCKS5 b:1080; DIRECT`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PacDirective {
+    Direct,
+    /// Proxy URL, e.g. `http://proxy.corp:8080`
+    Proxy(String),
+}
+
+/// Bun executable used to evaluate PAC scripts (registered at startup)
+static BUN_EXECUTABLE: OnceLock<PathBuf> = OnceLock::new();
+
+/// (PAC source, scheme, host) → (evaluated at, directives)
+type PacCache = HashMap<(String, String, String), (Instant, Vec<PacDirective>)>;
+static CACHE: OnceLock<Mutex<PacCache>> = OnceLock::new();
+
+/// Register the Bun runtime used for PAC evaluation
+pub fn set_bun_executable(path: PathBuf) {
+    let _ = BUN_EXECUTABLE.set(path);
+}
+
+/// Parse a `FindProxyForURL` result into directives
+/// Unknown directive types are skipped; an empty result means DIRECT
+pub fn parse_pac_result(result: &str) -> Vec<PacDirective> {
+    let mut directives = Vec::new();
+    for entry in result.split(';') {
+        let mut parts = entry.split_whitespace();
+        let Some(kind) = parts.next() else { continue };
+        let target = parts.next();
+        let scheme = match (kind.to_ascii_uppercase().as_str(), target) {
+            ("DIRECT", _) => {
+                directives.push(PacDirective::Direct);
+                continue;
+            }
+            ("PROXY" | "HTTP", Some(_)) => "http",
+            ("HTTPS", Some(_)) => "https",
+            ("SOCKS" | "SOCKS5", Some(_)) => "socks5",
+            _ => {
+                log::debug!("[pac] Skipping unsupported PAC directive: {}", entry.trim());
+                continue;
+            }
+        };
+        if let Some(target) = target {
+            directives.push(PacDirective::Proxy(format!("{}://{}", scheme, target)));
+        }
+    }
+    if directives.is_empty() {
+        directives.push(PacDirective::Direct);
+    }
+    directives
+}
+
+fn cache_key(pac_source: &str, url: &reqwest::Url) -> (String, String, String) {
+    let host = url.host_str().unwrap_or_default().to_string();
+    (pac_source.to_string(), url.scheme().to_string(), host)
+}
+
+/// First directive as a proxy URL (None = DIRECT)
+fn first_proxy(directives: Vec<PacDirective>) -> Option<String> {
+    match directives.into_iter().next() {
+        Some(PacDirective::Proxy(proxy_url)) => Some(proxy_url),
+        _ => None,
+    }
+}
+
+/// Cached result for `url` without evaluating anything (outer None = not cached)
+pub fn cached_proxy(pac_source: &str, url: &reqwest::Url) -> Option<Option<String>> {
+    let key = cache_key(pac_source, url);
+    let entries = CACHE.get_or_init(|| Mutex::new(HashMap::new())).lock().ok()?;
+    entries
+        .get(&key)
+        .filter(|(at, _)| at.elapsed() < Duration::from_secs(PAC_CACHE_TTL_SECS))
+        .map(|(_, directives)| first_proxy(directives.clone()))
+}
+
+/// Pick the proxy for `url` using the PAC script at `pac_source` (file path, file:// or http(s) URL)
+/// Returns None when the PAC script says DIRECT. Only the first directive is used:
+/// neither reqwest nor the sidecar's proxy env vars support failover lists.
+/// Blocks while evaluating; never call it on an async worker thread or under a lock.
+pub fn find_proxy(pac_source: &str, url: &reqwest::Url) -> Result<Option<String>, String> {
+    if let Some(cached) = cached_proxy(pac_source, url) {
+        return Ok(cached);
+    }
+
+    let key = cache_key(pac_source, url);
+    let result = evaluate(pac_source, url)?;
+    log::info!("[pac] FindProxyForURL({}) = {}", key.2, result);
+    let directives = parse_pac_result(&result);
+    if let Ok(mut entries) = CACHE.get_or_init(|| Mutex::new(HashMap::new())).lock() {
+        entries.insert(key, (Instant::now(), directives.clone()));
+    }
+    Ok(first_proxy(directives))
+}
+
+/// Run `FindProxyForURL` for `url` in a Bun subprocess
+fn evaluate(pac_source: &str, url: &reqwest::Url) -> Result<String, String> {
+    let bun = BUN_EXECUTABLE
+        .get()
+        .ok_or("PAC evaluation unavailable: bundled Bun runtime not found")?;
+    let host = url.host_str().unwrap_or_default();
+    let host_ip = resolve_ipv4(host, url.port_or_known_default().unwrap_or(443))
+        .map(|ip| ip.to_string())
+        .unwrap_or_default();
+
+    let mut cmd = Command::new(bun);
+    cmd.arg("-e")
+        .arg(PAC_RUNTIME_JS)
+        .env("MYAGENTS_PAC_SOURCE", pac_source)
+        .env("MYAGENTS_PAC_URL", url.as_str())
+        .env("MYAGENTS_PAC_HOST", host)
+        .env("MYAGENTS_PAC_HOST_IP", host_ip)
+        .env("MYAGENTS_PAC_MY_IP", local_ipv4().to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    for key in PROXY_ENV_VARS {
+        cmd.env_remove(key);
+    }
+
+    // Windows: Hide console window for GUI app
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start PAC evaluator: {}", e))?;
+
+    let Some((status, stdout, stderr)) =
+        wait_with_output(&mut child, Duration::from_secs(PAC_EVAL_TIMEOUT_SECS))?
+    else {
+        return Err(format!(
+            "PAC evaluation timed out after {}s ({})",
+            PAC_EVAL_TIMEOUT_SECS, pac_source
+        ));
+    };
+
+    if !status.success() {
+        return Err(format!("PAC evaluation failed ({}): {}", pac_source, stderr.trim()));
+    }
+    Ok(stdout.trim().to_string())
+}
+
+/// Wait for `child` while draining its stdout/stderr on reader threads, so a script
+/// printing more than a pipe buffer can't block itself; kills it after `timeout`
+/// Returns None on timeout, else the exit status and output (the last
+/// PAC_OUTPUT_MAX_BYTES of each stream).
+fn wait_with_output(child: &mut Child, timeout: Duration) -> Result<Option<(ExitStatus, String, String)>, String> {
+    let stdout = drain_pipe(child.stdout.take());
+    let stderr = drain_pipe(child.stderr.take());
+
+    let start = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if start.elapsed() > timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break None;
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            Err(e) => {
+                let _ = child.kill();
+                return Err(format!("Failed to wait for PAC evaluator: {}", e));
+            }
+        }
+    };
+
+    // The pipes close with the process, which ends the reader threads
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    Ok(status.map(|status| (status, stdout, stderr)))
+}
+
+/// Read `pipe` to the end on its own thread, keeping only its tail
+fn drain_pipe(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let Some(mut pipe) = pipe else {
+            return String::new();
+        };
+        let mut output = Vec::new();
+        let mut buf = [0u8; 8192];
+        loop {
+            match pipe.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => output.extend_from_slice(&buf[..n]),
+            }
+            if output.len() > 2 * PAC_OUTPUT_MAX_BYTES {
+                output.drain(..output.len() - PAC_OUTPUT_MAX_BYTES);
+            }
+        }
+        let keep = output.len().min(PAC_OUTPUT_MAX_BYTES);
+        String::from_utf8_lossy(&output[output.len() - keep..]).into_owned()
+    })
+}
+
+/// First IPv4 address of `host` (PAC's dnsResolve/isInNet are IPv4-only)
+fn resolve_ipv4(host: &str, port: u16) -> Option<IpAddr> {
+    (host, port)
+        .to_socket_addrs()
+        .ok()?
+        .map(|addr| addr.ip())
+        .find(IpAddr::is_ipv4)
+}
+
+/// Local IPv4 address of the default route, for PAC's myIpAddress()
+/// (connecting a UDP socket sends no packets, it only selects the outgoing interface)
+fn local_ipv4() -> IpAddr {
+    UdpSocket::bind("0.0.0.0:0")
+        .and_then(|socket| {
+            socket.connect("8.8.8.8:53")?;
+            socket.local_addr()
+        })
+        .map(|addr| addr.ip())
+        .unwrap_or(IpAddr::from([127, 0, 0, 1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pac_result_proxy_list() {
+        assert_eq!(
+            parse_pac_result("PROXY proxy.corp:8080; SOCKS5 socks.corp:1080; DIRECT"),
+            vec![
+                PacDirective::Proxy("http://proxy.corp:8080".to_string()),
+                PacDirective::Proxy("socks5://socks.corp:1080".to_string()),
+                PacDirective::Direct,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_pac_result_schemes() {
+        assert_eq!(
+            parse_pac_result("HTTPS secure.corp:443"),
+            vec![PacDirective::Proxy("https://secure.corp:443".to_string())]
+        );
+        assert_eq!(
+            parse_pac_result("socks s:1080"),
+            vec![PacDirective::Proxy("socks5://s:1080".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_pac_result_empty_is_direct() {
+        assert_eq!(parse_pac_result(""), vec![PacDirective::Direct]);
+        assert_eq!(parse_pac_result("  ;  "), vec![PacDirective::Direct]);
+    }
+
+    #[test]
+    fn test_parse_pac_result_skips_unsupported() {
+        assert_eq!(
+            parse_pac_result("QUIC q:443; PROXY; PROXY p:3128"),
+            vec![PacDirective::Proxy("http://p:3128".to_string())]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_chatty_evaluator_does_not_block_on_full_pipes() {
+        // Far more than a pipe buffer on both streams before the result
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg("head -c 2000000 /dev/zero | tr '\\0' x; head -c 2000000 /dev/zero >&2; printf 'DIRECT'")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let start = Instant::now();
+        let (status, stdout, stderr) = wait_with_output(&mut child, Duration::from_secs(10)).unwrap().unwrap();
+        assert!(status.success());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(stdout.ends_with("xDIRECT"));
+        assert_eq!(stdout.len(), PAC_OUTPUT_MAX_BYTES);
+        assert_eq!(stderr.len(), PAC_OUTPUT_MAX_BYTES);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hung_evaluator_times_out() {
+        let mut child = Command::new("sleep").arg("5").stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap();
+        assert!(wait_with_output(&mut child, Duration::from_millis(100)).unwrap().is_none());
+    }
+}
@@ -2,18 +2,21 @@
 // Handles spawning, monitoring, and shutting down multiple Bun backend server instances
 // Supports per-Tab isolation with independent Sidecar processes
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::sync::atomic::{AtomicU16, Ordering};
-use std::sync::{Arc, Mutex, Once};
+use std::sync::{mpsc, Arc, Mutex, Once};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use tauri::{AppHandle, Manager, Runtime};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
 
+use crate::app_config::{self, RuntimeSettings};
 use crate::proxy_config;
+use crate::tls_config;
 
 // Ensure file descriptor limit is increased only once
 static RLIMIT_INIT: Once = Once::new();
@@ -73,14 +76,30 @@ fn ensure_high_file_descriptor_limit() {
 }
 
 // Configuration constants
-const BASE_PORT: u16 = 31415;
-// Health check: 60 attempts × 100ms = 6 seconds total (optimized for faster startup)
-const HEALTH_CHECK_MAX_ATTEMPTS: u32 = 60;
+// Port range, health check attempts, shutdown timeout and restart budget are
+// configurable via runtimeSettings (see app_config.rs)
+// Health check: 60 attempts × 100ms = 6 seconds total by default
 const HEALTH_CHECK_DELAY_MS: u64 = 100;
 const HEALTH_CHECK_TIMEOUT_MS: u64 = 100;
-const GRACEFUL_SHUTDOWN_TIMEOUT_SECS: u64 = 5;
-// Port range: 500 ports (31415-31914)
-const PORT_RANGE: u16 = 500;
+// Read timeout for the /health response (connect uses HEALTH_CHECK_TIMEOUT_MS)
+const HEALTH_CHECK_READ_TIMEOUT_MS: u64 = 1000;
+// Readiness endpoint served by the sidecar (see src/server/index.ts)
+const HEALTH_CHECK_PATH: &str = "/health";
+// Liveness: probe every 10s, mark unhealthy after 3 consecutive failures
+const LIVENESS_PROBE_INTERVAL_SECS: u64 = 10;
+const LIVENESS_PROBE_TIMEOUT_MS: u64 = 2000;
+const LIVENESS_MAX_FAILURES: u32 = 3;
+// Port handshake: the sidecar binds the first free port starting at our hint and
+// prints this prefix followed by JSON {"port":N,"pid":N} on stdout (see src/server/index.ts)
+const SIDECAR_HANDSHAKE_PREFIX: &str = "MYAGENTS_SIDECAR_LISTENING ";
+const PORT_HANDSHAKE_TIMEOUT_SECS: u64 = 15;
+// Crash supervisor: how often exited processes are detected
+const SUPERVISOR_POLL_INTERVAL_MS: u64 = 1000;
+// Restart backoff: 500ms, 1s, 2s, 4s... capped at 30s
+const RESTART_BACKOFF_BASE_MS: u64 = 500;
+const RESTART_BACKOFF_MAX_MS: u64 = 30_000;
+// Number of recent stderr lines kept per instance for crash reports
+const STDERR_TAIL_LINES: usize = 50;
 // Special identifier for global sidecar (used by Settings page)
 pub const GLOBAL_SIDECAR_ID: &str = "__global__";
 // Process identification marker (used to identify our sidecar processes)
@@ -230,6 +249,18 @@ pub struct SidecarInstance {
     pub healthy: bool,
     /// Whether this is a global sidecar (uses temp directory)
     pub is_global: bool,
+    /// Whether the crash supervisor watches this instance (set once it first becomes healthy)
+    pub supervised: bool,
+    /// How long to wait for a graceful exit before force killing
+    pub shutdown_timeout: Duration,
+    /// Proxy and TLS environment the process was started with (compared on config changes)
+    pub network_env: Vec<(String, String)>,
+    /// Per-launch nonce echoed by the sidecar's /health endpoint
+    pub health_nonce: String,
+    /// Consecutive failed liveness probes
+    pub liveness_failures: u32,
+    /// Most recent stderr lines, included in crash reports
+    pub stderr_tail: Arc<Mutex<VecDeque<String>>>,
 }
 
 impl SidecarInstance {
@@ -260,7 +291,7 @@ impl SidecarInstance {
 impl Drop for SidecarInstance {
     fn drop(&mut self) {
         log::info!("[sidecar] Drop: killing process on port {}", self.port);
-        let _ = kill_process(&mut self.process);
+        let _ = kill_process(&mut self.process, self.shutdown_timeout);
         
         // Clean up temp directory for global sidecar
         if self.is_global {
@@ -272,41 +303,177 @@ impl Drop for SidecarInstance {
     }
 }
 
+/// Automatic restart policy applied by the crash supervisor
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    /// Maximum automatic restarts per Tab within `budget_window` (0 disables restarts)
+    pub max_attempts: u32,
+    /// Sliding window for counting restart attempts
+    pub budget_window: Duration,
+    /// Delay before the first restart, doubled for each further attempt
+    pub backoff_base: Duration,
+    /// Upper bound for the restart delay
+    pub backoff_max: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self::from(&RuntimeSettings::default())
+    }
+}
+
+impl From<&RuntimeSettings> for RestartPolicy {
+    fn from(settings: &RuntimeSettings) -> Self {
+        Self {
+            max_attempts: settings.sidecar_restart_max_attempts,
+            budget_window: Duration::from_secs(settings.sidecar_restart_window_secs),
+            backoff_base: Duration::from_millis(RESTART_BACKOFF_BASE_MS),
+            backoff_max: Duration::from_millis(RESTART_BACKOFF_MAX_MS),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Exponential backoff delay for the given attempt (1-based)
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.backoff_base.saturating_mul(factor).min(self.backoff_max)
+    }
+}
+
+/// Lifecycle notifications for state tied to a Tab's sidecar (SSE streams, see sse_proxy.rs)
+#[derive(Debug, Clone, PartialEq)]
+pub enum SidecarLifecycleEvent {
+    /// The instance passed its health check on `port` (first start or restart)
+    Healthy { tab_id: String, port: u16 },
+    /// The instance on `port` was stopped or has crashed
+    Stopped { tab_id: String, port: u16 },
+}
+
+/// Callback registered with SidecarManager::add_lifecycle_listener
+/// Runs with the manager locked, so it must not block or lock the manager itself.
+pub type SidecarLifecycleListener = Box<dyn Fn(&SidecarLifecycleEvent) + Send + Sync>;
+
+/// A supervised instance whose process exited without being stopped
+struct CrashedSidecar {
+    tab_id: String,
+    port: u16,
+    /// Agent directory to restart with (None for global sidecar)
+    agent_dir: Option<PathBuf>,
+    status: ExitStatus,
+    stderr_tail: Vec<String>,
+}
+
+/// Payload of the `sidecar:{tab_id}:crashed` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarCrashInfo {
+    pub tab_id: String,
+    pub port: u16,
+    /// Exit code (None if the process was terminated by a signal)
+    pub exit_code: Option<i32>,
+    /// Terminating signal on Unix (e.g. 11 for SIGSEGV)
+    pub signal: Option<i32>,
+    /// Human-readable exit status
+    pub status: String,
+    /// Last stderr lines captured before the crash
+    pub stderr_tail: Vec<String>,
+    /// Whether an automatic restart is scheduled
+    pub will_restart: bool,
+    /// Restart attempt number within the current budget window
+    pub restart_attempt: u32,
+    /// Delay before the scheduled restart
+    pub restart_delay_ms: Option<u64>,
+}
+
+/// Payload of the `sidecar:{tab_id}:restarted` and `sidecar:{tab_id}:restart-failed` events
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarRestartInfo {
+    pub tab_id: String,
+    /// Why the sidecar was restarted: "crash" or "config"
+    pub reason: &'static str,
+    /// Restart attempt within the budget window (0 for config restarts)
+    pub attempt: u32,
+    /// New port (restarted only)
+    pub port: Option<u16>,
+    /// Failure reason (restart-failed only)
+    pub error: Option<String>,
+    /// Whether another attempt is scheduled (restart-failed only)
+    pub will_retry: bool,
+}
+
+/// Payload of the `sidecar:{tab_id}:unhealthy` and `sidecar:{tab_id}:healthy` events
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarHealthInfo {
+    pub tab_id: String,
+    pub port: u16,
+    /// Consecutive failed liveness probes
+    pub consecutive_failures: u32,
+    /// Last probe error (unhealthy only)
+    pub error: Option<String>,
+}
+
 /// Multi-instance Sidecar Manager
 /// Manages multiple Sidecar processes, one per Tab
 pub struct SidecarManager {
     /// Tab ID -> Sidecar Instance
     instances: HashMap<String, SidecarInstance>,
-    /// Port counter for allocation (starts from BASE_PORT)
+    /// Port counter for allocation (starts from runtimeSettings.basePort)
     port_counter: AtomicU16,
+    /// Automatic restart policy for crashed instances
+    restart_policy: RestartPolicy,
+    /// Tab ID -> timestamps of recent automatic restarts (for the restart budget)
+    restart_history: HashMap<String, Vec<Instant>>,
+    /// Tabs with a scheduled restart; removing an entry cancels the restart
+    pending_restarts: HashSet<String>,
+    /// Notified when an instance becomes healthy or goes away
+    lifecycle_listeners: Vec<SidecarLifecycleListener>,
 }
 
 impl SidecarManager {
     pub fn new() -> Self {
         Self {
             instances: HashMap::new(),
-            port_counter: AtomicU16::new(BASE_PORT),
+            port_counter: AtomicU16::new(RuntimeSettings::default().base_port),
+            restart_policy: RestartPolicy::default(),
+            restart_history: HashMap::new(),
+            pending_restarts: HashSet::new(),
+            lifecycle_listeners: Vec::new(),
         }
     }
 
-    /// Get the next available port with max attempts to prevent infinite loop
-    fn allocate_port(&self) -> Result<u16, String> {
-        const MAX_ATTEMPTS: u32 = 200;
-        
-        for _ in 0..MAX_ATTEMPTS {
-            let port = self.port_counter.fetch_add(1, Ordering::SeqCst);
-            
-            // Reset counter if we've gone past the range
-            if port > BASE_PORT + PORT_RANGE {
-                self.port_counter.store(BASE_PORT, Ordering::SeqCst);
-            }
-            
-            if is_port_available(port) {
-                return Ok(port);
-            }
+    /// Register a callback for instance lifecycle changes
+    pub fn add_lifecycle_listener(&mut self, listener: SidecarLifecycleListener) {
+        self.lifecycle_listeners.push(listener);
+    }
+
+    fn notify_lifecycle(&self, event: SidecarLifecycleEvent) {
+        for listener in &self.lifecycle_listeners {
+            listener(&event);
         }
-        
-        Err(format!("No available port found after {} attempts", MAX_ATTEMPTS))
+    }
+
+    /// Replace the automatic restart policy
+    pub fn set_restart_policy(&mut self, policy: RestartPolicy) {
+        self.restart_policy = policy;
+    }
+
+    /// Get the next port hint, rotating through the configured range
+    /// Nothing is bound here: the sidecar binds the first free port from the hint to the
+    /// end of the range itself and reports it over the stdout handshake, so a port is
+    /// never probed, released and then handed over (no TOCTOU window)
+    fn next_port_hint(&self, settings: &RuntimeSettings) -> u16 {
+        let (base_port, range_end) = (settings.base_port, settings.port_range_end());
+        let port = self.port_counter.fetch_add(1, Ordering::SeqCst);
+
+        // Reset counter if we've gone past the range (or the range was reconfigured)
+        if port < base_port || port > range_end {
+            self.port_counter.store(base_port + 1, Ordering::SeqCst);
+            return base_port;
+        }
+        port
     }
 
     /// Check if a Tab has a running instance
@@ -335,6 +502,11 @@ impl SidecarManager {
         self.instances.remove(tab_id)
     }
 
+    /// Whether a sidecar instance currently owns `port`
+    pub fn owns_port(&self, port: u16) -> bool {
+        self.instances.values().any(|instance| instance.port == port)
+    }
+
     /// Get all Tab IDs
     #[allow(dead_code)]
     pub fn tab_ids(&self) -> Vec<String> {
@@ -344,7 +516,139 @@ impl SidecarManager {
     /// Stop all instances
     pub fn stop_all(&mut self) {
         log::info!("[sidecar] Stopping all {} instances", self.instances.len());
-        self.instances.clear(); // Drop will kill each process
+        self.pending_restarts.clear();
+        self.restart_history.clear();
+        let instances: Vec<(String, SidecarInstance)> = self.instances.drain().collect();
+        for (tab_id, instance) in instances {
+            self.notify_lifecycle(SidecarLifecycleEvent::Stopped { tab_id, port: instance.port });
+            // Drop kills the process
+        }
+    }
+
+    /// Remove supervised instances whose process has exited on its own
+    /// Instances that are stopped intentionally are removed before they exit,
+    /// so anything found here is a crash
+    fn take_crashed(&mut self) -> Vec<CrashedSidecar> {
+        let exited: Vec<(String, ExitStatus)> = self
+            .instances
+            .iter_mut()
+            .filter(|(_, instance)| instance.supervised)
+            .filter_map(|(tab_id, instance)| match instance.process.try_wait() {
+                Ok(Some(status)) => Some((tab_id.clone(), status)),
+                _ => None,
+            })
+            .collect();
+
+        let crashed: Vec<CrashedSidecar> = exited
+            .into_iter()
+            .filter_map(|(tab_id, status)| {
+                let instance = self.instances.remove(&tab_id)?;
+                let stderr_tail = instance
+                    .stderr_tail
+                    .lock()
+                    .map(|tail| tail.iter().cloned().collect())
+                    .unwrap_or_default();
+                Some(CrashedSidecar {
+                    port: instance.port,
+                    agent_dir: if instance.is_global { None } else { instance.agent_dir.clone() },
+                    tab_id,
+                    status,
+                    stderr_tail,
+                })
+                // Instance is dropped here (process already exited)
+            })
+            .collect();
+
+        for crash in &crashed {
+            self.notify_lifecycle(SidecarLifecycleEvent::Stopped { tab_id: crash.tab_id.clone(), port: crash.port });
+        }
+        crashed
+    }
+
+    /// Targets for liveness probing: (tab_id, port, nonce) of supervised, still-running instances
+    fn liveness_targets(&mut self) -> Vec<(String, u16, String)> {
+        self.instances
+            .iter_mut()
+            .filter(|(_, instance)| instance.supervised)
+            .filter_map(|(tab_id, instance)| match instance.process.try_wait() {
+                Ok(None) => Some((tab_id.clone(), instance.port, instance.health_nonce.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The instance a start_tab_sidecar call spawned, unless it was stopped or
+    /// replaced (a restart) while the caller waited without the lock
+    fn started_instance_mut(&mut self, tab_id: &str, health_nonce: &str) -> Option<&mut SidecarInstance> {
+        self.instances.get_mut(tab_id).filter(|i| i.health_nonce == health_nonce)
+    }
+
+    /// Mark a freshly started instance healthy and supervised, and notify listeners
+    /// Returns false (and changes nothing) if that instance is gone or moved ports.
+    fn mark_started(&mut self, tab_id: &str, health_nonce: &str, port: u16) -> bool {
+        let Some(instance) = self.started_instance_mut(tab_id, health_nonce).filter(|i| i.port == port) else {
+            return false;
+        };
+        instance.healthy = true;
+        instance.supervised = true;
+        self.notify_lifecycle(SidecarLifecycleEvent::Healthy { tab_id: tab_id.to_string(), port });
+        true
+    }
+
+    /// Record a liveness probe result and update the healthy flag
+    /// Returns Some(new healthy state) when the state flipped
+    fn record_liveness(&mut self, tab_id: &str, port: u16, ok: bool) -> Option<bool> {
+        // Ignore results for an instance that was replaced while probing
+        let instance = self.instances.get_mut(tab_id).filter(|i| i.port == port)?;
+
+        if ok {
+            instance.liveness_failures = 0;
+            if !instance.healthy {
+                instance.healthy = true;
+                return Some(true);
+            }
+        } else {
+            instance.liveness_failures += 1;
+            if instance.healthy && instance.liveness_failures >= LIVENESS_MAX_FAILURES {
+                instance.healthy = false;
+                return Some(false);
+            }
+        }
+        None
+    }
+
+    /// Reserve an automatic restart for a Tab within the restart budget
+    /// Returns the attempt number and backoff delay, or None if the budget is exhausted
+    fn reserve_restart(&mut self, tab_id: &str) -> Option<(u32, Duration)> {
+        let policy = &self.restart_policy;
+        let history = self.restart_history.entry(tab_id.to_string()).or_default();
+        history.retain(|t| t.elapsed() < policy.budget_window);
+
+        if history.len() as u32 >= policy.max_attempts {
+            return None;
+        }
+
+        history.push(Instant::now());
+        let attempt = history.len() as u32;
+        self.pending_restarts.insert(tab_id.to_string());
+        Some((attempt, policy.backoff_delay(attempt)))
+    }
+
+    /// Schedule a settings-driven restart (not counted against the crash budget)
+    /// stop_tab_sidecar cancels it like a crash restart.
+    fn schedule_restart(&mut self, tab_id: &str) {
+        self.pending_restarts.insert(tab_id.to_string());
+    }
+
+    /// Consume a scheduled restart; returns false if it was cancelled meanwhile
+    fn take_pending_restart(&mut self, tab_id: &str) -> bool {
+        self.pending_restarts.remove(tab_id)
+    }
+
+    /// Cancel any scheduled restart and forget the restart history of a Tab
+    fn cancel_restart(&mut self, tab_id: &str) {
+        self.pending_restarts.remove(tab_id);
+        self.restart_history.remove(tab_id);
     }
 }
 
@@ -410,8 +714,8 @@ pub struct LegacySidecarConfig {
 
 // ============= Core Functions =============
 
-/// Kill a child process gracefully, then forcefully
-fn kill_process(child: &mut Child) -> std::io::Result<()> {
+/// Kill a child process gracefully, then forcefully after `timeout`
+fn kill_process(child: &mut Child, timeout: Duration) -> std::io::Result<()> {
     #[cfg(unix)]
     {
         unsafe {
@@ -424,7 +728,6 @@ fn kill_process(child: &mut Child) -> std::io::Result<()> {
     }
 
     // Wait for graceful shutdown
-    let timeout = Duration::from_secs(GRACEFUL_SHUTDOWN_TIMEOUT_SECS);
     let start = std::time::Instant::now();
 
     loop {
@@ -447,13 +750,8 @@ fn kill_process(child: &mut Child) -> std::io::Result<()> {
     }
 }
 
-/// Check if a port is available
-fn is_port_available(port: u16) -> bool {
-    std::net::TcpListener::bind(format!("127.0.0.1:{}", port)).is_ok()
-}
-
 /// Find the bun executable path
-fn find_bun_executable<R: Runtime>(app_handle: &AppHandle<R>) -> Option<PathBuf> {
+pub fn find_bun_executable<R: Runtime>(app_handle: &AppHandle<R>) -> Option<PathBuf> {
     // First, try to find bundled bun
     if let Ok(resource_dir) = app_handle.path().resource_dir() {
         #[cfg(target_os = "macos")]
@@ -645,21 +943,163 @@ fn find_server_script<R: Runtime>(_app_handle: &AppHandle<R>) -> Option<PathBuf>
     None
 }
 
+/// Probe the sidecar's readiness endpoint
+/// Succeeds only if it answers 200 with `{"status":"ok"}` and echoes our per-launch nonce,
+/// which proves the listener on this port is our child (not another process squatting on it)
+fn probe_health(
+    port: u16,
+    nonce: &str,
+    connect_timeout: Duration,
+    read_timeout: Duration,
+) -> Result<(), String> {
+    use std::io::{Read, Write};
+
+    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+    let mut stream = std::net::TcpStream::connect_timeout(&addr, connect_timeout)
+        .map_err(|e| format!("connect failed: {}", e))?;
+    stream.set_read_timeout(Some(read_timeout)).map_err(|e| e.to_string())?;
+    stream.set_write_timeout(Some(read_timeout)).map_err(|e| e.to_string())?;
+
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nAccept: application/json\r\nConnection: close\r\n\r\n",
+        HEALTH_CHECK_PATH, port
+    );
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| format!("write failed: {}", e))?;
+
+    let mut response = Vec::new();
+    stream
+        .read_to_end(&mut response)
+        .map_err(|e| format!("read failed: {}", e))?;
+
+    check_health_response(&response, nonce)
+}
+
+/// Validate a raw HTTP response from the /health endpoint
+fn check_health_response(response: &[u8], nonce: &str) -> Result<(), String> {
+    let text = String::from_utf8_lossy(response);
+    let (head, body) = text
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| "malformed HTTP response".to_string())?;
+
+    let status = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| "malformed HTTP status line".to_string())?;
+    if status != 200 {
+        return Err(format!("unexpected HTTP status {}", status));
+    }
+
+    let json: serde_json::Value =
+        serde_json::from_str(body.trim()).map_err(|e| format!("invalid JSON body: {}", e))?;
+    if json.get("status").and_then(|v| v.as_str()) != Some("ok") {
+        return Err(format!("sidecar not ready: {}", json));
+    }
+    if json.get("nonce").and_then(|v| v.as_str()) != Some(nonce) {
+        return Err("nonce mismatch (port is served by another process)".to_string());
+    }
+
+    Ok(())
+}
+
+/// Parse the port handshake line printed by the sidecar once it is listening
+fn parse_port_handshake(line: &str) -> Option<u16> {
+    let payload = line.trim().strip_prefix(SIDECAR_HANDSHAKE_PREFIX.trim_end())?;
+    let json: serde_json::Value = serde_json::from_str(payload.trim()).ok()?;
+    json.get("port")
+        .and_then(|p| p.as_u64())
+        .and_then(|p| u16::try_from(p).ok())
+        .filter(|&p| p != 0)
+}
+
+/// Wait for the sidecar to report the port it actually bound
+fn wait_for_listen_port(port_rx: &mpsc::Receiver<u16>) -> Result<u16, String> {
+    match port_rx.recv_timeout(Duration::from_secs(PORT_HANDSHAKE_TIMEOUT_SECS)) {
+        Ok(port) => Ok(port),
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(format!(
+            "Sidecar did not report its listening port within {}s",
+            PORT_HANDSHAKE_TIMEOUT_SECS
+        )),
+        // stdout closed before the handshake: the process exited during startup
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            Err("Sidecar exited before reporting its listening port".to_string())
+        }
+    }
+}
+
+/// Proxy environment variables for the Bun sidecar (see `proxy_config::sidecar_proxy_env`)
+/// Returns an empty list (direct connection) when no proxy applies
+fn sidecar_proxy_env() -> Vec<(String, String)> {
+    match proxy_config::sidecar_proxy_env() {
+        Ok(env) if env.is_empty() => {
+            log::debug!("[sidecar] No proxy configured, using direct connection");
+            env
+        }
+        Ok(env) => {
+            if let Some((_, proxy_url)) = env.iter().find(|(k, _)| k == "HTTPS_PROXY" || k == "HTTP_PROXY") {
+                log::info!(
+                    "[sidecar] Injecting proxy for Claude Agent SDK: {}",
+                    proxy_config::redact_proxy_url(proxy_url)
+                );
+            }
+            env
+        }
+        Err(e) => {
+            // Invalid proxy configuration (bad protocol, port, mode, PAC, etc.)
+            // Log as error since user explicitly enabled proxy but config is invalid
+            log::error!(
+                "[sidecar] Invalid proxy configuration: {}. \
+                 Please check Settings > About > Developer Mode > Proxy Settings. \
+                 Sidecar will start without proxy.",
+                e
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// TLS environment variables for the Bun sidecar (see `tls_config::sidecar_tls_env`)
+/// Returns an empty list (Bun's bundled roots only) when the TLS settings are invalid
+fn sidecar_tls_env() -> Vec<(String, String)> {
+    tls_config::sidecar_tls_env().unwrap_or_else(|e| {
+        log::error!(
+            "[sidecar] Invalid TLS configuration: {}. \
+             Sidecar will start with Bun's bundled certificate authorities only.",
+            e
+        );
+        Vec::new()
+    })
+}
+
+/// Proxy and TLS environment variables for the Bun sidecar
+fn sidecar_network_env() -> Vec<(String, String)> {
+    let mut env = sidecar_proxy_env();
+    env.extend(sidecar_tls_env());
+    env
+}
+
 /// Wait for a sidecar to become healthy
-fn wait_for_health(port: u16) -> Result<(), String> {
+fn wait_for_health(port: u16, nonce: &str, max_attempts: u32) -> Result<(), String> {
     let delay = Duration::from_millis(HEALTH_CHECK_DELAY_MS);
+    let mut last_error = String::new();
 
-    for attempt in 1..=HEALTH_CHECK_MAX_ATTEMPTS {
-        match std::net::TcpStream::connect_timeout(
-            &format!("127.0.0.1:{}", port).parse().unwrap(),
+    for attempt in 1..=max_attempts {
+        match probe_health(
+            port,
+            nonce,
             Duration::from_millis(HEALTH_CHECK_TIMEOUT_MS),
+            Duration::from_millis(HEALTH_CHECK_READ_TIMEOUT_MS),
         ) {
-            Ok(_) => {
+            Ok(()) => {
                 log::info!("[sidecar] Healthy after {} attempts on port {}", attempt, port);
                 return Ok(());
             }
-            Err(_) => {
-                if attempt < HEALTH_CHECK_MAX_ATTEMPTS {
+            Err(e) => {
+                last_error = e;
+                if attempt < max_attempts {
                     thread::sleep(delay);
                 }
             }
@@ -667,8 +1107,8 @@ fn wait_for_health(port: u16) -> Result<(), String> {
     }
 
     Err(format!(
-        "Sidecar failed to become healthy after {} attempts",
-        HEALTH_CHECK_MAX_ATTEMPTS
+        "Sidecar failed to become healthy after {} attempts (last error: {})",
+        max_attempts, last_error
     ))
 }
 
@@ -684,6 +1124,11 @@ pub fn start_tab_sidecar<R: Runtime>(
     // Ensure file descriptor limit is high enough for Bun
     ensure_high_file_descriptor_limit();
 
+    let runtime = app_config::runtime_settings();
+    // Proxy and TLS environment; may evaluate a PAC script (subprocess, DNS), so it is
+    // resolved before taking the manager lock
+    let network_env = sidecar_network_env();
+
     let mut manager_guard = manager.lock().map_err(|e| e.to_string())?;
 
     // Check if already running for this tab
@@ -695,7 +1140,9 @@ pub fn start_tab_sidecar<R: Runtime>(
     }
 
     // Remove stale instance if exists
-    manager_guard.remove_instance(tab_id);
+    if let Some(stale) = manager_guard.remove_instance(tab_id) {
+        manager_guard.notify_lifecycle(SidecarLifecycleEvent::Stopped { tab_id: tab_id.to_string(), port: stale.port });
+    }
 
     // Find executables
     let bun_path = find_bun_executable(app_handle)
@@ -703,20 +1150,26 @@ pub fn start_tab_sidecar<R: Runtime>(
     let script_path = find_server_script(app_handle)
         .ok_or_else(|| "Server script not found".to_string())?;
 
-    // Allocate port
-    let port = manager_guard.allocate_port()?;
+    // Pick a port hint (the sidecar reports the port it actually binds)
+    let port_hint = manager_guard.next_port_hint(&runtime);
+    let port_range_end = runtime.port_range_end();
 
     log::info!(
-        "[sidecar] Starting for tab {} on port {}, agent_dir: {:?}",
-        tab_id, port, agent_dir
+        "[sidecar] Starting for tab {} from port {}, agent_dir: {:?}",
+        tab_id, port_hint, agent_dir
     );
 
     // Build command - 直接用 bun <script> 而非 bun run <script>（更稳定）
     // Add SIDECAR_MARKER for reliable process identification and cleanup
+    let health_nonce = uuid::Uuid::new_v4().to_string();
     let mut cmd = Command::new(&bun_path);
     cmd.arg(&script_path)
         .arg("--port")
-        .arg(port.to_string())
+        .arg(port_hint.to_string())
+        .arg("--port-range-end")
+        .arg(port_range_end.max(port_hint).to_string())
+        .arg("--health-nonce")
+        .arg(&health_nonce)
         .arg(SIDECAR_MARKER);
 
     // Determine if this is a global sidecar and handle agent directory
@@ -752,34 +1205,12 @@ pub fn start_tab_sidecar<R: Runtime>(
         log::info!("[sidecar] Working directory set to: {:?}", script_dir);
     }
 
-    // Inject proxy environment variables if configured
-    if let Some(proxy_settings) = proxy_config::read_proxy_settings() {
-        match proxy_config::get_proxy_url(&proxy_settings) {
-            Ok(proxy_url) => {
-                log::info!("[sidecar] Injecting proxy for Claude Agent SDK: {}", proxy_url);
-                cmd.env("HTTP_PROXY", &proxy_url);
-                cmd.env("HTTPS_PROXY", &proxy_url);
-                cmd.env("http_proxy", &proxy_url);
-                cmd.env("https_proxy", &proxy_url);
-                // Ensure localhost traffic doesn't go through proxy
-                // Comprehensive NO_PROXY list for maximum compatibility
-                cmd.env("NO_PROXY", "localhost,localhost.localdomain,127.0.0.1,127.0.0.0/8,::1,[::1]");
-                cmd.env("no_proxy", "localhost,localhost.localdomain,127.0.0.1,127.0.0.0/8,::1,[::1]");
-            }
-            Err(e) => {
-                // Invalid proxy configuration (bad protocol, port, etc.)
-                // Log as error since user explicitly enabled proxy but config is invalid
-                log::error!(
-                    "[sidecar] Invalid proxy configuration: {}. \
-                     Please check Settings > About > Developer Mode > Proxy Settings. \
-                     Sidecar will start without proxy.",
-                    e
-                );
-            }
-        }
-    } else {
-        log::debug!("[sidecar] No proxy configured, using direct connection");
+    // Inject proxy and TLS environment variables if configured
+    // Inherited ones are dropped so the configured settings (incl. direct) always win
+    for key in proxy_config::PROXY_ENV_VARS.iter().chain(tls_config::TLS_ENV_VARS.iter()) {
+        cmd.env_remove(key);
     }
+    cmd.envs(network_env.iter().map(|(k, v)| (k.as_str(), v.as_str())));
 
     cmd.stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -797,8 +1228,8 @@ pub fn start_tab_sidecar<R: Runtime>(
     log::info!("[sidecar] current_exe = {:?}", std::env::current_exe().ok());
 
     log::info!(
-        "[sidecar] Spawning: bun={:?}, script={:?}, port={}, is_global={}",
-        bun_path, script_path, port, is_global
+        "[sidecar] Spawning: bun={:?}, script={:?}, port_hint={}, is_global={}",
+        bun_path, script_path, port_hint, is_global
     );
 
     // Spawn
@@ -810,23 +1241,44 @@ pub fn start_tab_sidecar<R: Runtime>(
     log::info!("[sidecar] Process spawned with pid: {:?}", child.id());
 
     // 启动线程捕获 stdout
+    // The first handshake line carries the port the sidecar actually bound
+    let (port_tx, port_rx) = mpsc::channel::<u16>();
     if let Some(stdout) = child.stdout.take() {
         let tab_id_clone = tab_id.to_string();
         thread::spawn(move || {
+            let mut port_tx = Some(port_tx);
             let reader = BufReader::new(stdout);
-            for line in reader.lines().flatten() {
+            for line in reader.lines().map_while(Result::ok) {
+                if port_tx.is_some() {
+                    if let Some(port) = parse_port_handshake(&line) {
+                        log::info!("[sidecar] Tab {} reported listening port {}", tab_id_clone, port);
+                        if let Some(tx) = port_tx.take() {
+                            let _ = tx.send(port);
+                        }
+                        continue;
+                    }
+                }
                 log::info!("[bun-out][{}] {}", tab_id_clone, line);
             }
         });
     }
 
     // 启动线程捕获 stderr（关键：这里会打印 Bun 的错误信息）
+    // Recent lines are also kept in a ring buffer for crash reports
+    let stderr_tail = Arc::new(Mutex::new(VecDeque::with_capacity(STDERR_TAIL_LINES)));
     if let Some(stderr) = child.stderr.take() {
         let tab_id_clone = tab_id.to_string();
+        let stderr_tail = stderr_tail.clone();
         thread::spawn(move || {
             let reader = BufReader::new(stderr);
-            for line in reader.lines().flatten() {
+            for line in reader.lines().map_while(Result::ok) {
                 log::error!("[bun-err][{}] {}", tab_id_clone, line);
+                if let Ok(mut tail) = stderr_tail.lock() {
+                    if tail.len() >= STDERR_TAIL_LINES {
+                        tail.pop_front();
+                    }
+                    tail.push_back(line);
+                }
             }
         });
     }
@@ -841,13 +1293,19 @@ pub fn start_tab_sidecar<R: Runtime>(
         return Err(format!("Bun process exited immediately with status: {:?}", status));
     }
 
-    // Create instance (not yet healthy)
+    // Create instance (not yet healthy, port is updated once the handshake arrives)
     let instance = SidecarInstance {
         process: child,
-        port,
+        port: port_hint,
         agent_dir: effective_agent_dir,
         healthy: false,
         is_global,
+        supervised: false,
+        shutdown_timeout: Duration::from_secs(runtime.graceful_shutdown_timeout_secs),
+        network_env,
+        health_nonce: health_nonce.clone(),
+        liveness_failures: 0,
+        stderr_tail,
     };
 
     manager_guard.insert_instance(tab_id.to_string(), instance);
@@ -855,13 +1313,23 @@ pub fn start_tab_sidecar<R: Runtime>(
     // Drop lock before waiting for health
     drop(manager_guard);
 
-    // Wait for health
-    match wait_for_health(port) {
-        Ok(()) => {
-            // Mark as healthy
+    // Wait for the port handshake, then for health on the reported port
+    let startup = wait_for_listen_port(&port_rx).and_then(|port| {
+        if let Ok(mut manager_guard) = manager.lock() {
+            if let Some(instance) = manager_guard.started_instance_mut(tab_id, &health_nonce) {
+                instance.port = port;
+            }
+        }
+        wait_for_health(port, &health_nonce, runtime.health_check_max_attempts).map(|()| port)
+    });
+
+    match startup {
+        Ok(port) => {
+            // Mark as healthy, unless stop_tab_sidecar (or a restart) got there first
             let mut manager_guard = manager.lock().map_err(|e| e.to_string())?;
-            if let Some(instance) = manager_guard.get_instance_mut(tab_id) {
-                instance.healthy = true;
+            if !manager_guard.mark_started(tab_id, &health_nonce, port) {
+                log::warn!("[sidecar] Tab {} was stopped while starting on port {}", tab_id, port);
+                return Err("sidecar stopped during startup".to_string());
             }
             Ok(port)
         }
@@ -871,27 +1339,28 @@ pub fn start_tab_sidecar<R: Runtime>(
             
             // Try to get the instance and check if process is still running
             let mut manager_guard = manager.lock().map_err(|_| e.clone())?;
-            if let Some(instance) = manager_guard.get_instance_mut(tab_id) {
-                // Check if process has exited
-                match instance.process.try_wait() {
-                    Ok(Some(status)) => {
-                        log::error!("[sidecar] Process exited with status: {:?}", status);
-                    }
-                    Ok(None) => {
-                        log::error!("[sidecar] Process still running but not healthy");
-                    }
-                    Err(wait_err) => {
-                        log::error!("[sidecar] Failed to check process status: {}", wait_err);
-                    }
+            let Some(instance) = manager_guard.started_instance_mut(tab_id, &health_nonce) else {
+                // Already stopped or replaced by a newer start: nothing of ours to clean up
+                return Err(e);
+            };
+            // Check if process has exited
+            match instance.process.try_wait() {
+                Ok(Some(status)) => {
+                    log::error!("[sidecar] Process exited with status: {:?}", status);
                 }
-                
-                // Try to read stderr if available
-                if let Some(ref mut stderr) = instance.process.stderr.take() {
-                    use std::io::Read;
-                    let mut output = String::new();
-                    if stderr.read_to_string(&mut output).is_ok() && !output.is_empty() {
-                        log::error!("[sidecar] Process stderr: {}", output);
-                    }
+                Ok(None) => {
+                    log::error!("[sidecar] Process still running but not healthy");
+                }
+                Err(wait_err) => {
+                    log::error!("[sidecar] Failed to check process status: {}", wait_err);
+                }
+            }
+            
+            // Dump stderr captured so far
+            if let Ok(tail) = instance.stderr_tail.lock() {
+                if !tail.is_empty() {
+                    let output: Vec<&str> = tail.iter().map(String::as_str).collect();
+                    log::error!("[sidecar] Process stderr:\n{}", output.join("\n"));
                 }
             }
             
@@ -906,9 +1375,13 @@ pub fn start_tab_sidecar<R: Runtime>(
 /// Stop a Sidecar for a specific Tab
 pub fn stop_tab_sidecar(manager: &ManagedSidecarManager, tab_id: &str) -> Result<(), String> {
     let mut manager_guard = manager.lock().map_err(|e| e.to_string())?;
-    
+
+    // Intentional stop: cancel any pending automatic restart
+    manager_guard.cancel_restart(tab_id);
+
     if let Some(instance) = manager_guard.remove_instance(tab_id) {
         log::info!("[sidecar] Stopped instance for tab {} on port {}", tab_id, instance.port);
+        manager_guard.notify_lifecycle(SidecarLifecycleEvent::Stopped { tab_id: tab_id.to_string(), port: instance.port });
         // Instance is dropped here, killing the process
     }
     
@@ -957,6 +1430,337 @@ pub fn start_global_sidecar<R: Runtime>(
     start_tab_sidecar(app_handle, manager, GLOBAL_SIDECAR_ID, None)
 }
 
+// ============= Crash Supervisor =============
+
+/// Spawn the background supervisor that watches every instance in the manager
+/// When a healthy process exits unexpectedly it emits `sidecar:{tab_id}:crashed`
+/// and restarts it on the same agent_dir with exponential backoff.
+/// It also runs periodic liveness probes against each instance's /health endpoint.
+pub fn spawn_sidecar_supervisor<R: Runtime>(
+    app_handle: AppHandle<R>,
+    manager: ManagedSidecarManager,
+) {
+    let result = thread::Builder::new()
+        .name("sidecar-supervisor".to_string())
+        .spawn(move || {
+            let liveness_interval = Duration::from_secs(LIVENESS_PROBE_INTERVAL_SECS);
+            let mut last_liveness_probe = Instant::now();
+
+            loop {
+                thread::sleep(Duration::from_millis(SUPERVISOR_POLL_INTERVAL_MS));
+
+                let crashed = match manager.lock() {
+                    Ok(mut manager_guard) => manager_guard.take_crashed(),
+                    Err(e) => {
+                        log::error!("[sidecar] Supervisor failed to lock manager: {}", e);
+                        continue;
+                    }
+                };
+
+                for crash in crashed {
+                    handle_sidecar_crash(&app_handle, &manager, crash);
+                }
+
+                if last_liveness_probe.elapsed() >= liveness_interval {
+                    last_liveness_probe = Instant::now();
+                    run_liveness_probes(&app_handle, &manager);
+                }
+            }
+        });
+
+    if let Err(e) = result {
+        log::error!("[sidecar] Failed to spawn supervisor thread: {}", e);
+    }
+}
+
+/// Probe every supervised instance and flip its healthy flag when the server stops
+/// (or resumes) responding. Probes run without holding the manager lock, one thread
+/// per instance, so a tick takes as long as the slowest probe (not their sum).
+fn run_liveness_probes<R: Runtime>(app_handle: &AppHandle<R>, manager: &ManagedSidecarManager) {
+    let targets = match manager.lock() {
+        Ok(mut manager_guard) => manager_guard.liveness_targets(),
+        Err(_) => return,
+    };
+
+    let timeout = Duration::from_millis(LIVENESS_PROBE_TIMEOUT_MS);
+    let results: Vec<(String, u16, Result<(), String>)> = thread::scope(|scope| {
+        let probes: Vec<_> = targets
+            .into_iter()
+            .map(|(tab_id, port, nonce)| {
+                let probe = scope.spawn(move || probe_health(port, &nonce, timeout, timeout));
+                (tab_id, port, probe)
+            })
+            .collect();
+        probes
+            .into_iter()
+            .map(|(tab_id, port, probe)| {
+                let result = probe.join().unwrap_or_else(|_| Err("probe thread panicked".to_string()));
+                (tab_id, port, result)
+            })
+            .collect()
+    });
+
+    for (tab_id, port, result) in results {
+        if let Err(ref e) = result {
+            log::warn!("[sidecar] Liveness probe failed for tab {} on port {}: {}", tab_id, port, e);
+        }
+
+        let (flipped, failures) = match manager.lock() {
+            Ok(mut manager_guard) => {
+                let flipped = manager_guard.record_liveness(&tab_id, port, result.is_ok());
+                let failures = manager_guard
+                    .get_instance(&tab_id)
+                    .map(|i| i.liveness_failures)
+                    .unwrap_or(0);
+                (flipped, failures)
+            }
+            Err(_) => return,
+        };
+
+        let Some(healthy) = flipped else { continue };
+        let event = if healthy { "healthy" } else { "unhealthy" };
+        if healthy {
+            log::info!("[sidecar] Tab {} on port {} is responding again", tab_id, port);
+        } else {
+            log::error!(
+                "[sidecar] Tab {} on port {} stopped responding ({} failed probes)",
+                tab_id, port, failures
+            );
+        }
+        let info = SidecarHealthInfo {
+            tab_id: tab_id.clone(),
+            port,
+            consecutive_failures: failures,
+            error: result.err(),
+        };
+        let _ = app_handle.emit(&format!("sidecar:{}:{}", tab_id, event), &info);
+    }
+}
+
+/// Split an exit status into (exit code, signal)
+fn exit_status_parts(status: &ExitStatus) -> (Option<i32>, Option<i32>) {
+    #[cfg(unix)]
+    let signal = {
+        use std::os::unix::process::ExitStatusExt;
+        status.signal()
+    };
+    #[cfg(not(unix))]
+    let signal = None;
+
+    (status.code(), signal)
+}
+
+/// Reserve a restart slot using the restart budget currently configured in config.json
+fn reserve_restart(manager: &ManagedSidecarManager, tab_id: &str) -> Option<(u32, Duration)> {
+    let policy = RestartPolicy::from(&app_config::runtime_settings());
+    let mut manager_guard = manager.lock().ok()?;
+    manager_guard.set_restart_policy(policy);
+    manager_guard.reserve_restart(tab_id)
+}
+
+/// Report a crash to the frontend and schedule a restart if the budget allows
+fn handle_sidecar_crash<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    manager: &ManagedSidecarManager,
+    crash: CrashedSidecar,
+) {
+    let (exit_code, signal) = exit_status_parts(&crash.status);
+    let reservation = reserve_restart(manager, &crash.tab_id);
+
+    log::error!(
+        "[sidecar] Tab {} crashed on port {} ({}), restart: {:?}",
+        crash.tab_id, crash.port, crash.status, reservation
+    );
+
+    let info = SidecarCrashInfo {
+        tab_id: crash.tab_id.clone(),
+        port: crash.port,
+        exit_code,
+        signal,
+        status: crash.status.to_string(),
+        stderr_tail: crash.stderr_tail,
+        will_restart: reservation.is_some(),
+        restart_attempt: reservation.map(|(attempt, _)| attempt).unwrap_or(0),
+        restart_delay_ms: reservation.map(|(_, delay)| delay.as_millis() as u64),
+    };
+    if let Err(e) = app_handle.emit(&format!("sidecar:{}:crashed", crash.tab_id), &info) {
+        log::error!("[sidecar] Failed to emit crash event for tab {}: {}", crash.tab_id, e);
+    }
+
+    let Some((attempt, delay)) = reservation else {
+        log::error!(
+            "[sidecar] Tab {} exhausted its restart budget, not restarting",
+            crash.tab_id
+        );
+        return;
+    };
+
+    let app_handle = app_handle.clone();
+    let manager = manager.clone();
+    thread::spawn(move || {
+        restart_crashed_sidecar(&app_handle, &manager, &crash.tab_id, crash.agent_dir, attempt, delay);
+    });
+}
+
+/// Restart a crashed sidecar after the backoff delay, retrying failed starts within the budget
+fn restart_crashed_sidecar<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    manager: &ManagedSidecarManager,
+    tab_id: &str,
+    agent_dir: Option<PathBuf>,
+    mut attempt: u32,
+    mut delay: Duration,
+) {
+    loop {
+        thread::sleep(delay);
+
+        // The Tab may have been stopped (or the app is exiting) while we were waiting
+        let still_pending = manager
+            .lock()
+            .map(|mut manager_guard| manager_guard.take_pending_restart(tab_id))
+            .unwrap_or(false);
+        if !still_pending {
+            log::info!("[sidecar] Restart of tab {} cancelled", tab_id);
+            return;
+        }
+
+        log::info!("[sidecar] Restarting tab {} (attempt {})", tab_id, attempt);
+        let error = match start_tab_sidecar(app_handle, manager, tab_id, agent_dir.clone()) {
+            Ok(port) => {
+                log::info!("[sidecar] Tab {} restarted on port {}", tab_id, port);
+                let info = SidecarRestartInfo {
+                    tab_id: tab_id.to_string(),
+                    reason: "crash",
+                    attempt,
+                    port: Some(port),
+                    error: None,
+                    will_retry: false,
+                };
+                let _ = app_handle.emit(&format!("sidecar:{}:restarted", tab_id), &info);
+                return;
+            }
+            Err(e) => e,
+        };
+
+        let next = reserve_restart(manager, tab_id);
+        log::error!(
+            "[sidecar] Restart of tab {} failed (attempt {}): {}",
+            tab_id, attempt, error
+        );
+        let info = SidecarRestartInfo {
+            tab_id: tab_id.to_string(),
+            reason: "crash",
+            attempt,
+            port: None,
+            error: Some(error),
+            will_retry: next.is_some(),
+        };
+        let _ = app_handle.emit(&format!("sidecar:{}:restart-failed", tab_id), &info);
+
+        match next {
+            Some((next_attempt, next_delay)) => {
+                attempt = next_attempt;
+                delay = next_delay;
+            }
+            None => return,
+        }
+    }
+}
+
+// ============= Config Changes =============
+
+/// React to a config.json change: restart sidecars whose proxy/TLS environment changed
+/// so the new settings apply without the user restarting Tabs by hand
+pub fn apply_config_change<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    manager: &ManagedSidecarManager,
+    change: &app_config::ConfigChange,
+) {
+    if change.touches("proxySettings") || change.touches("tlsSettings") {
+        restart_sidecars_with_stale_network_env(app_handle, manager);
+    }
+}
+
+/// Restart running sidecars whose proxy/TLS environment no longer matches the current
+/// settings (after a proxySettings/tlsSettings change or a new proxy password)
+pub fn restart_sidecars_with_stale_network_env<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    manager: &ManagedSidecarManager,
+) {
+    let network_env = sidecar_network_env();
+    let stale: Vec<(String, Option<PathBuf>)> = match manager.lock() {
+        Ok(mut manager_guard) => {
+            let stale: Vec<(String, Option<PathBuf>)> = manager_guard
+                .instances
+                .iter()
+                .filter(|(_, instance)| instance.healthy && instance.network_env != network_env)
+                .map(|(tab_id, instance)| {
+                    let agent_dir = if instance.is_global { None } else { instance.agent_dir.clone() };
+                    (tab_id.clone(), agent_dir)
+                })
+                .collect();
+            // Closing a Tab while its restart is in flight cancels the restart
+            for (tab_id, _) in &stale {
+                manager_guard.schedule_restart(tab_id);
+            }
+            stale
+        }
+        Err(_) => return,
+    };
+
+    if stale.is_empty() {
+        return;
+    }
+
+    log::info!(
+        "[sidecar] Network settings changed, restarting {} sidecar(s) to apply them",
+        stale.len()
+    );
+
+    let app_handle = app_handle.clone();
+    let manager = manager.clone();
+    thread::spawn(move || {
+        for (tab_id, agent_dir) in stale {
+            // Take the instance out only if the Tab still wants its sidecar
+            let replaced = {
+                let Ok(mut manager_guard) = manager.lock() else { return };
+                if !manager_guard.take_pending_restart(&tab_id) {
+                    log::info!("[sidecar] Config restart of tab {} cancelled (tab stopped)", tab_id);
+                    continue;
+                }
+                let replaced = manager_guard.remove_instance(&tab_id);
+                if let Some(instance) = &replaced {
+                    manager_guard.notify_lifecycle(SidecarLifecycleEvent::Stopped {
+                        tab_id: tab_id.clone(),
+                        port: instance.port,
+                    });
+                }
+                replaced
+            };
+            // Kill the old process outside the lock
+            drop(replaced);
+
+            let (port, error) = match start_tab_sidecar(&app_handle, &manager, &tab_id, agent_dir) {
+                Ok(port) => (Some(port), None),
+                Err(e) => {
+                    log::error!("[sidecar] Failed to restart tab {} after config change: {}", tab_id, e);
+                    (None, Some(e))
+                }
+            };
+            let event = if port.is_some() { "restarted" } else { "restart-failed" };
+            let info = SidecarRestartInfo {
+                tab_id: tab_id.clone(),
+                reason: "config",
+                attempt: 0,
+                port,
+                error,
+                will_retry: false,
+            };
+            let _ = app_handle.emit(&format!("sidecar:{}:{}", tab_id, event), &info);
+        }
+    });
+}
+
 /// Stop all sidecar instances and clean up child processes
 /// This should be called when the app is closing
 pub fn stop_all_sidecars(manager: &ManagedSidecarManager) -> Result<(), String> {
@@ -1180,3 +1984,184 @@ pub fn ensure_sidecar_running<R: Runtime>(
     // Need to restart
     restart_sidecar(app_handle, state)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_backoff_is_exponential_and_capped() {
+        let policy = RestartPolicy::default();
+        assert_eq!(policy.backoff_delay(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff_delay(2), Duration::from_millis(1000));
+        assert_eq!(policy.backoff_delay(4), Duration::from_millis(4000));
+        assert_eq!(policy.backoff_delay(20), Duration::from_millis(RESTART_BACKOFF_MAX_MS));
+    }
+
+    #[test]
+    fn test_restart_budget_is_enforced() {
+        let mut manager = SidecarManager::new();
+        manager.set_restart_policy(RestartPolicy {
+            max_attempts: 2,
+            ..RestartPolicy::default()
+        });
+
+        assert_eq!(manager.reserve_restart("tab").map(|(a, _)| a), Some(1));
+        assert_eq!(manager.reserve_restart("tab").map(|(a, _)| a), Some(2));
+        assert!(manager.reserve_restart("tab").is_none());
+
+        // Budget is tracked per Tab
+        assert!(manager.reserve_restart("other").is_some());
+    }
+
+    #[test]
+    fn test_port_hints_rotate_through_the_range() {
+        let manager = SidecarManager::new();
+        let settings = RuntimeSettings { base_port: 40000, port_range: 2, ..RuntimeSettings::default() };
+        let hints: Vec<u16> = (0..5).map(|_| manager.next_port_hint(&settings)).collect();
+        assert_eq!(hints, vec![40000, 40001, 40002, 40000, 40001]);
+    }
+
+    #[test]
+    fn test_parse_port_handshake() {
+        assert_eq!(
+            parse_port_handshake("MYAGENTS_SIDECAR_LISTENING {\"port\":31420,\"pid\":7}"),
+            Some(31420)
+        );
+        assert_eq!(parse_port_handshake("MYAGENTS_SIDECAR_LISTENING {\"port\":0}"), None);
+        assert_eq!(parse_port_handshake("MYAGENTS_SIDECAR_LISTENING garbage"), None);
+        assert_eq!(parse_port_handshake("Web UI server listening on http://localhost:31420"), None);
+    }
+
+    #[test]
+    fn test_health_response_accepts_matching_nonce() {
+        let response = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{\"status\":\"ok\",\"nonce\":\"abc\",\"pid\":42}";
+        assert!(check_health_response(response, "abc").is_ok());
+    }
+
+    #[test]
+    fn test_health_response_rejects_foreign_listener() {
+        let wrong_nonce = b"HTTP/1.1 200 OK\r\n\r\n{\"status\":\"ok\",\"nonce\":\"other\"}";
+        assert!(check_health_response(wrong_nonce, "abc").unwrap_err().contains("nonce mismatch"));
+
+        let not_found = b"HTTP/1.1 404 Not Found\r\n\r\nNot Found";
+        assert!(check_health_response(not_found, "abc").unwrap_err().contains("404"));
+
+        let not_json = b"HTTP/1.1 200 OK\r\n\r\n<html></html>";
+        assert!(check_health_response(not_json, "abc").unwrap_err().contains("invalid JSON"));
+
+        let not_ready = b"HTTP/1.1 200 OK\r\n\r\n{\"status\":\"starting\",\"nonce\":\"abc\"}";
+        assert!(check_health_response(not_ready, "abc").unwrap_err().contains("not ready"));
+    }
+
+    #[test]
+    fn test_cancel_restart_clears_pending_and_budget() {
+        let mut manager = SidecarManager::new();
+        assert!(manager.reserve_restart("tab").is_some());
+
+        manager.cancel_restart("tab");
+        assert!(!manager.take_pending_restart("tab"));
+
+        // Settings-driven restarts are cancelled the same way
+        manager.schedule_restart("tab");
+        manager.cancel_restart("tab");
+        assert!(!manager.take_pending_restart("tab"));
+        assert_eq!(manager.reserve_restart("tab").map(|(a, _)| a), Some(1));
+        assert!(manager.take_pending_restart("tab"));
+    }
+
+    /// Supervised instance whose process already exited
+    #[cfg(unix)]
+    fn exited_instance(port: u16) -> SidecarInstance {
+        let mut process = Command::new("true").spawn().unwrap();
+        process.wait().unwrap();
+        SidecarInstance {
+            process,
+            port,
+            agent_dir: None,
+            healthy: true,
+            is_global: false,
+            supervised: true,
+            shutdown_timeout: Duration::from_millis(10),
+            network_env: Vec::new(),
+            health_nonce: String::new(),
+            liveness_failures: 0,
+            stderr_tail: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Manager whose lifecycle events are collected in the returned list
+    #[cfg(unix)]
+    fn recording_manager() -> (SidecarManager, Arc<Mutex<Vec<SidecarLifecycleEvent>>>) {
+        let mut manager = SidecarManager::new();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        manager.add_lifecycle_listener(Box::new(move |event| recorded.lock().unwrap().push(event.clone())));
+        (manager, events)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_crashed_instances_notify_lifecycle_listeners() {
+        let (mut manager, events) = recording_manager();
+        manager.insert_instance("tab".to_string(), exited_instance(31415));
+
+        assert_eq!(manager.take_crashed().len(), 1);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![SidecarLifecycleEvent::Stopped { tab_id: "tab".to_string(), port: 31415 }]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stop_all_notifies_every_stopped_tab() {
+        let (mut manager, events) = recording_manager();
+        manager.insert_instance("a".to_string(), exited_instance(31415));
+        manager.insert_instance("b".to_string(), exited_instance(31416));
+
+        manager.stop_all();
+        assert!(manager.tab_ids().is_empty());
+        let mut stopped = events.lock().unwrap().clone();
+        stopped.sort_by_key(|event| match event {
+            SidecarLifecycleEvent::Stopped { port, .. } | SidecarLifecycleEvent::Healthy { port, .. } => *port,
+        });
+        assert_eq!(
+            stopped,
+            vec![
+                SidecarLifecycleEvent::Stopped { tab_id: "a".to_string(), port: 31415 },
+                SidecarLifecycleEvent::Stopped { tab_id: "b".to_string(), port: 31416 },
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stale_start_does_not_mark_a_stopped_or_restarted_tab_healthy() {
+        let (mut manager, events) = recording_manager();
+        let starting = |nonce: &str| {
+            let mut instance = exited_instance(31415);
+            instance.healthy = false;
+            instance.supervised = false;
+            instance.health_nonce = nonce.to_string();
+            instance
+        };
+
+        // Stopped while waiting for health
+        assert!(!manager.mark_started("tab", "first", 31415));
+
+        // Restarted meanwhile: the newer start owns the tab
+        manager.insert_instance("tab".to_string(), starting("second"));
+        assert!(!manager.mark_started("tab", "first", 31415));
+        assert!(!manager.get_instance("tab").unwrap().healthy);
+        assert!(events.lock().unwrap().is_empty());
+
+        assert!(manager.mark_started("tab", "second", 31415));
+        let instance = manager.get_instance("tab").unwrap();
+        assert!(instance.healthy && instance.supervised);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![SidecarLifecycleEvent::Healthy { tab_id: "tab".to_string(), port: 31415 }]
+        );
+    }
+}
@@ -0,0 +1,449 @@
+//! Incremental Server-Sent Events decoder
+//!
+//! Byte-level implementation of the WHATWG "event stream interpretation" algorithm
+//! (https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation):
+//! - Lines end with CRLF, LF or CR, including a CR/LF pair split across chunks
+//! - A leading UTF-8 BOM is ignored
+//! - Lines are decoded as UTF-8 only once complete, so multi-byte characters split
+//!   across network chunks (common with CJK text) are never corrupted
+//! - `event`, `data`, `id` (ignored when it contains NUL) and `retry` (ASCII digits only)
+//!   fields; comments (`:` lines) are reported so heartbeats can be observed
+//! - An event with an empty data buffer is not dispatched, but its `id` still counts
+//!
+//! Cost is linear in the input: lines completed inside a chunk are parsed straight from
+//! the chunk, and only a line spanning chunks is copied into a reusable buffer
+//! (see `benches/sse_decoder.rs`).
+
+/// UTF-8 byte order mark
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+/// Line buffer capacity kept after a line completes; larger buffers (from a huge
+/// tool output line) are released instead of pinning memory for the stream's lifetime
+const LINE_BUFFER_RETAIN_BYTES: usize = 64 * 1024;
+
+/// A dispatched event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Event type ("message" when not set)
+    pub event: String,
+    /// Data lines joined with "\n"
+    pub data: String,
+    /// Last event ID at dispatch time (None if no `id` was seen yet)
+    pub id: Option<String>,
+}
+
+/// Output of the decoder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SseItem {
+    Event(SseEvent),
+    /// `retry:` reconnection time in milliseconds
+    Retry(u64),
+    /// Comment line (without the leading `:`), e.g. the sidecar's `: ping` heartbeat
+    Comment(String),
+}
+
+/// Incremental decoder; feed it network chunks in order
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    /// Bytes of the current line when it spans chunks
+    line: Vec<u8>,
+    /// Previous chunk ended with CR: a leading LF belongs to that line break
+    pending_cr: bool,
+    /// No line has been completed yet (BOM check)
+    started: bool,
+    event_type: String,
+    data: String,
+    /// Whether any `data` field was seen for the current event
+    has_data: bool,
+    last_event_id: Option<String>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Last event ID seen so far (sent back as `Last-Event-ID` on reconnect)
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    /// Bytes buffered for the current incomplete line
+    pub fn pending_bytes(&self) -> usize {
+        self.line.len()
+    }
+
+    /// Decode a chunk, returning the items it completes
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseItem> {
+        let mut items = Vec::new();
+        let mut rest = chunk;
+
+        // An empty chunk keeps the pending CR for the next one
+        if self.pending_cr && !rest.is_empty() {
+            self.pending_cr = false;
+            if let Some(stripped) = rest.strip_prefix(b"\n") {
+                rest = stripped;
+            }
+        }
+
+        while let Some(pos) = rest.iter().position(|&b| b == b'\n' || b == b'\r') {
+            if self.line.is_empty() {
+                // Fast path: the whole line is inside this chunk
+                self.process_line(&rest[..pos], &mut items);
+            } else {
+                self.line.extend_from_slice(&rest[..pos]);
+                let line = std::mem::take(&mut self.line);
+                self.process_line(&line, &mut items);
+                self.reuse_line_buffer(line);
+            }
+
+            let is_cr = rest[pos] == b'\r';
+            rest = &rest[pos + 1..];
+            if is_cr {
+                match rest.first() {
+                    Some(b'\n') => rest = &rest[1..],
+                    None => self.pending_cr = true,
+                    Some(_) => {}
+                }
+            }
+        }
+        self.line.extend_from_slice(rest);
+        items
+    }
+
+    /// Keep the line buffer's allocation for the next spanning line (unless it grew huge)
+    fn reuse_line_buffer(&mut self, mut line: Vec<u8>) {
+        if line.capacity() <= LINE_BUFFER_RETAIN_BYTES {
+            line.clear();
+            self.line = line;
+        }
+    }
+
+    fn process_line(&mut self, line: &[u8], items: &mut Vec<SseItem>) {
+        let line = if self.started {
+            line
+        } else {
+            self.started = true;
+            line.strip_prefix(UTF8_BOM).unwrap_or(line)
+        };
+
+        if line.is_empty() {
+            self.dispatch(items);
+            return;
+        }
+        if let Some(comment) = line.strip_prefix(b":") {
+            let comment = comment.strip_prefix(b" ").unwrap_or(comment);
+            items.push(SseItem::Comment(String::from_utf8_lossy(comment).into_owned()));
+            return;
+        }
+
+        let (field, value) = match line.iter().position(|&b| b == b':') {
+            Some(colon) => {
+                let value = &line[colon + 1..];
+                (&line[..colon], value.strip_prefix(b" ").unwrap_or(value))
+            }
+            None => (line, &[][..]),
+        };
+
+        match field {
+            b"event" => self.event_type = String::from_utf8_lossy(value).into_owned(),
+            b"data" => {
+                self.data.push_str(&String::from_utf8_lossy(value));
+                self.data.push('\n');
+                self.has_data = true;
+            }
+            b"id" if !value.contains(&0) => {
+                self.last_event_id = Some(String::from_utf8_lossy(value).into_owned());
+            }
+            b"retry" if !value.is_empty() && value.iter().all(u8::is_ascii_digit) => {
+                // Saturate absurdly long values instead of dropping them
+                let millis = std::str::from_utf8(value)
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(u64::MAX);
+                items.push(SseItem::Retry(millis));
+            }
+            _ => {}
+        }
+    }
+
+    fn dispatch(&mut self, items: &mut Vec<SseItem>) {
+        let event_type = std::mem::take(&mut self.event_type);
+        let mut data = std::mem::take(&mut self.data);
+        if !std::mem::take(&mut self.has_data) {
+            return;
+        }
+        data.pop(); // trailing "\n"
+        items.push(SseItem::Event(SseEvent {
+            event: if event_type.is_empty() { "message".to_string() } else { event_type },
+            data,
+            id: self.last_event_id.clone(),
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_all(input: &[u8]) -> Vec<SseItem> {
+        SseDecoder::new().feed(input)
+    }
+
+    fn decode_chunked(input: &[u8], splits: &[usize]) -> Vec<SseItem> {
+        let mut decoder = SseDecoder::new();
+        let mut items = Vec::new();
+        let mut start = 0;
+        for &end in splits {
+            items.extend(decoder.feed(&input[start..end]));
+            start = end;
+        }
+        items.extend(decoder.feed(&input[start..]));
+        items
+    }
+
+    fn event(event: &str, data: &str, id: Option<&str>) -> SseItem {
+        SseItem::Event(SseEvent {
+            event: event.to_string(),
+            data: data.to_string(),
+            id: id.map(str::to_string),
+        })
+    }
+
+    /// Small deterministic PRNG (xorshift64*) for property and fuzz tests
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+
+        fn splits(&mut self, len: usize) -> Vec<usize> {
+            let mut splits: Vec<usize> = (0..self.below(8)).map(|_| self.below(len + 1)).collect();
+            splits.sort_unstable();
+            splits
+        }
+    }
+
+    #[test]
+    fn test_spec_examples() {
+        // Examples from the WHATWG spec
+        let items = decode_all(b"data: YHOO\ndata: +2\ndata: 10\n\n");
+        assert_eq!(items, vec![event("message", "YHOO\n+2\n10", None)]);
+
+        let items = decode_all(
+            b": test stream\n\ndata: first event\nid: 1\n\ndata:second event\nid\n\ndata:  third event\n\n",
+        );
+        assert_eq!(
+            items,
+            vec![
+                SseItem::Comment("test stream".to_string()),
+                event("message", "first event", Some("1")),
+                event("message", "second event", Some("")),
+                event("message", " third event", Some("")),
+            ]
+        );
+
+        // "data" without colon is an empty data line; the last event has no blank line
+        let items = decode_all(b"data\n\ndata\ndata\n\ndata:");
+        assert_eq!(items, vec![event("message", "", None), event("message", "\n", None)]);
+
+        let items = decode_all(b"data:test\n\ndata: test\n\n");
+        assert_eq!(items, vec![event("message", "test", None), event("message", "test", None)]);
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let expected = vec![event("chat:message-chunk", "a\nb", None), event("message", "c", None)];
+        for input in [
+            &b"event: chat:message-chunk\ndata: a\ndata: b\n\ndata: c\n\n"[..],
+            b"event: chat:message-chunk\r\ndata: a\r\ndata: b\r\n\r\ndata: c\r\n\r\n",
+            b"event: chat:message-chunk\rdata: a\rdata: b\r\rdata: c\r\r",
+            b"event: chat:message-chunk\r\ndata: a\rdata: b\n\r\ndata: c\n\r",
+        ] {
+            assert_eq!(decode_all(input), expected, "{:?}", String::from_utf8_lossy(input));
+        }
+        // CRLF split across chunks (even with an empty chunk between) is one line break
+        assert_eq!(decode_chunked(b"data: x\r\n\r\n", &[8]), vec![event("message", "x", None)]);
+        assert_eq!(decode_chunked(b"data: x\r\n\r\n", &[8, 8]), vec![event("message", "x", None)]);
+        assert_eq!(decode_chunked(b"data: x\r\r", &[8]), vec![event("message", "x", None)]);
+    }
+
+    #[test]
+    fn test_bom_is_stripped_once() {
+        let input = b"\xEF\xBB\xBFdata: a\n\n\xEF\xBB\xBFdata: b\n\n";
+        for split in 0..=input.len() {
+            // The second BOM makes the field name unknown, so that event is ignored
+            assert_eq!(decode_chunked(input, &[split]), vec![event("message", "a", None)]);
+        }
+    }
+
+    #[test]
+    fn test_event_type_and_id_rules() {
+        let items = decode_all(b"event: chat:init\nid: 7\ndata: {}\n\nevent: ignored\n\ndata: x\n\n");
+        assert_eq!(
+            items,
+            vec![event("chat:init", "{}", Some("7")), event("message", "x", Some("7"))]
+        );
+
+        // id without data updates the last event ID without dispatching
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.feed(b"id: 9\n\n").is_empty());
+        assert_eq!(decoder.last_event_id(), Some("9"));
+
+        // ids containing NUL are ignored
+        assert!(decoder.feed(b"id: 1\x002\n\n").is_empty());
+        assert_eq!(decoder.last_event_id(), Some("9"));
+    }
+
+    #[test]
+    fn test_retry_field() {
+        assert_eq!(decode_all(b"retry: 1500\n"), vec![SseItem::Retry(1500)]);
+        assert!(decode_all(b"retry: 15s\nretry:\nretry: -1\n").is_empty());
+        assert_eq!(decode_all(b"retry: 99999999999999999999999\n"), vec![SseItem::Retry(u64::MAX)]);
+    }
+
+    #[test]
+    fn test_unknown_fields_and_heartbeats() {
+        let items = decode_all(b": ping\n\nfoo: bar\ndata: x\n\n");
+        assert_eq!(items, vec![SseItem::Comment("ping".to_string()), event("message", "x", None)]);
+    }
+
+    #[test]
+    fn test_multibyte_utf8_split_at_every_byte() {
+        let input = "event: chat:message-chunk\ndata: 你好，世界！🎉 中文流式输出\n\n".as_bytes();
+        let expected = vec![event("chat:message-chunk", "你好，世界！🎉 中文流式输出", None)];
+        for split in 0..=input.len() {
+            assert_eq!(decode_chunked(input, &[split]), expected, "split at {}", split);
+        }
+        // One byte at a time
+        let splits: Vec<usize> = (1..input.len()).collect();
+        assert_eq!(decode_chunked(input, &splits), expected);
+    }
+
+    #[test]
+    fn test_line_buffer_only_holds_partial_lines() {
+        let mut decoder = SseDecoder::new();
+        assert_eq!(decoder.feed(b"data: complete\n\ndata: par"), vec![event("message", "complete", None)]);
+        assert_eq!(decoder.pending_bytes(), b"data: par".len());
+        assert_eq!(decoder.feed(b"tial\n\n"), vec![event("message", "partial", None)]);
+        assert_eq!(decoder.pending_bytes(), 0);
+
+        // A huge spanning line is decoded whole and its buffer released afterwards
+        let big = "界".repeat(LINE_BUFFER_RETAIN_BYTES);
+        let input = format!("data: {}\n\n", big);
+        let mut items = Vec::new();
+        for chunk in input.as_bytes().chunks(4096) {
+            items.extend(decoder.feed(chunk));
+        }
+        assert_eq!(items, vec![event("message", &big, None)]);
+        assert!(decoder.line.capacity() <= LINE_BUFFER_RETAIN_BYTES);
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_replaced_per_line() {
+        let items = decode_all(b"data: a\xFFb\n\n");
+        assert_eq!(items, vec![event("message", "a\u{FFFD}b", None)]);
+    }
+
+    /// Property: encoding arbitrary events and decoding them under any chunking
+    /// yields the same events
+    #[test]
+    fn test_property_round_trip_under_random_chunking() {
+        const PIECES: [&str; 10] = ["a", "中", "文", "🎉", " ", ":", "é", "x y", "{\"k\":1}", "ping"];
+        const NEWLINES: [&str; 3] = ["\n", "\r\n", "\r"];
+        let mut rng = Rng(0x5EED_1234_ABCD_0001);
+
+        for _ in 0..500 {
+            let mut stream = String::new();
+            let mut expected = Vec::new();
+            let mut last_id: Option<String> = None;
+            let nl = NEWLINES[rng.below(NEWLINES.len())];
+
+            for n in 0..rng.below(6) {
+                let name = format!("chat:event-{}", n);
+                let lines: Vec<String> = (0..1 + rng.below(3))
+                    .map(|_| (0..rng.below(6)).map(|_| PIECES[rng.below(PIECES.len())]).collect())
+                    .collect();
+                if rng.below(2) == 0 {
+                    stream.push_str(&format!(": heartbeat{}", nl));
+                    expected.push(SseItem::Comment("heartbeat".to_string()));
+                }
+                if rng.below(2) == 0 {
+                    let id = format!("{}", rng.next() % 1000);
+                    stream.push_str(&format!("id: {}{}", id, nl));
+                    last_id = Some(id);
+                }
+                stream.push_str(&format!("event: {}{}", name, nl));
+                for line in &lines {
+                    stream.push_str(&format!("data: {}{}", line, nl));
+                }
+                stream.push_str(nl);
+                expected.push(event(&name, &lines.join("\n"), last_id.as_deref()));
+            }
+
+            let bytes = stream.as_bytes();
+            for _ in 0..10 {
+                let splits = rng.splits(bytes.len());
+                assert_eq!(decode_chunked(bytes, &splits), expected, "stream {:?} splits {:?}", stream, splits);
+            }
+        }
+    }
+
+    /// Fuzz: arbitrary bytes never panic and decode identically however they are chunked
+    #[test]
+    fn test_fuzz_arbitrary_bytes_are_chunking_invariant() {
+        const ALPHABET: &[u8] = b"data:event:id:retry: \r\n\n\r:0123\xEF\xBB\xBF\xE4\xB8\xAD\xFF\x00";
+        let mut rng = Rng(0xF022_2026_0000_0042);
+
+        for _ in 0..2000 {
+            let len = rng.below(200);
+            let bytes: Vec<u8> = (0..len)
+                .map(|_| {
+                    if rng.below(4) == 0 {
+                        rng.next() as u8
+                    } else {
+                        ALPHABET[rng.below(ALPHABET.len())]
+                    }
+                })
+                .collect();
+            let whole = decode_all(&bytes);
+            let splits = rng.splits(bytes.len());
+            assert_eq!(decode_chunked(&bytes, &splits), whole, "input {:?} splits {:?}", bytes, splits);
+        }
+    }
+
+    /// Timing-sensitive, so not part of the default run:
+    /// `cargo test --release sse_decoder -- --ignored` (benches/sse_decoder.rs has details)
+    #[test]
+    #[ignore]
+    fn test_throughput_stays_linear_with_large_bursts() {
+        const MB: usize = 1024 * 1024;
+        let line = format!("event: chat:tool-result-delta\ndata: {{\"delta\":\"{}\"}}\n\n", "x".repeat(1000));
+        let time_per_mb = |mb: usize| {
+            let input = line.repeat(mb * MB / line.len() + 1).into_bytes();
+            (0..3)
+                .map(|_| {
+                    let start = std::time::Instant::now();
+                    let mut decoder = SseDecoder::new();
+                    for chunk in input.chunks(4 * MB) {
+                        std::hint::black_box(decoder.feed(chunk));
+                    }
+                    start.elapsed()
+                })
+                .min()
+                .unwrap()
+                .as_secs_f64()
+                / mb as f64
+        };
+
+        let growth = time_per_mb(16) / time_per_mb(1);
+        assert!(growth < 3.0, "time per MB grew {:.2}x from 1 MB to 16 MB", growth);
+    }
+}
@@ -1,42 +1,555 @@
 // SSE Proxy module - Connects to sidecar SSE and forwards events via Tauri
 // This bypasses WebView CORS restrictions entirely
 // Supports multiple connections (one per Tab)
+//
+// Delivery: start_sse_proxy called with a `channel` pushes typed, ordered SseMessage
+// values to that webview only. Without one, events are emitted app-wide as
+// `sse:{tab}:{event}` (every window receives them) - kept as a fallback for older callers.
+//
+// Fan-out: each Tab has one upstream connection feeding an SseHub. Every
+// start_sse_proxy call subscribes to the hub (a detached log viewer, a mini-window...)
+// and gets a subscriber id; stop_sse_proxy with that id unsubscribes, and the upstream
+// connection closes when the last subscriber leaves. Late subscribers first receive the
+// most recent events (bounded by SSE_REPLAY_MAX_MESSAGES / SSE_REPLAY_MAX_BYTES).
+//
+// Sidecar binding: Tab streams follow the sidecar lifecycle (bind_to_sidecars). Once a
+// Tab's sidecar is healthy its `/chat/stream` is attached automatically; when the sidecar
+// stops the upstream connection is torn down, and after a restart (possibly on another
+// port) it is reattached to the same hub, so subscribers keep receiving events. The
+// frontend only passes a tab id; a bound stream stays open without subscribers (the
+// replay buffer covers whoever subscribes next).
+//
+// Recordings: replay_sse_recording (sse_recorder.rs) publishes into a separate hub per
+// Tab that has no upstream connection, so a replay works without a sidecar and never
+// mixes with live events. start_sse_proxy / stop_sse_proxy with `replay` address it.
 
-use std::collections::HashMap;
-use std::error::Error;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use tauri::{AppHandle, Emitter};
+use std::time::Duration;
+use tauri::ipc::{Channel, JavaScriptChannelId};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::app_config;
+use crate::http_clients::{self, ClientPurpose};
+use crate::sidecar::{ManagedSidecarManager, SidecarLifecycleEvent, GLOBAL_SIDECAR_ID};
+use crate::sse_decoder::{SseDecoder, SseItem};
+use crate::sse_recorder::SseRecorder;
 
-// Timeout constants (in seconds)
+// Timeouts are configurable via runtimeSettings (see app_config.rs)
 //
-// SSE_READ_TIMEOUT: Idle timeout for SSE connections
+// sseReadTimeoutSecs: Idle timeout for SSE connections (default 60s)
 // - Backend sends heartbeat every 15s
 // - 60s gives 4x margin to handle network jitter
-// - If no data received for 60s, connection is considered dead
+// - If no data received within the timeout, connection is considered dead
 //
-// HTTP_PROXY_TIMEOUT: Total timeout for HTTP proxy requests
-// - 120s (2 minutes) allows for slow API responses
-// - Covers model generation time for complex requests
-//
-// TODO v0.2.0: Make these configurable via Settings
-const SSE_READ_TIMEOUT_SECS: u64 = 60;
-const HTTP_PROXY_TIMEOUT_SECS: u64 = 120;
+// Stall detection: heartbeats (`: ping` comments) only prove the sidecar is alive, so
+// they are tracked apart from events (SseActivity). While a reply is in progress (from
+// its first delta until chat:message-complete / -stopped / -error), going
+// runtimeSettings.sseStallTimeoutSecs without any event emits `sse:{tab}:stalled` once;
+// its payload tells whether heartbeats kept arriving meanwhile.
+
+// Reconnection: stream errors, read timeouts and server-side closes reconnect with
+// jittered exponential backoff (base delay overridable by the stream's `retry:` field), sending `Last-Event-ID` so the sidecar can replay
+// missed events. Emits `sse:{tab}:reconnecting` / `sse:{tab}:reconnected`; only after
+// SSE_RECONNECT_MAX_ATTEMPTS consecutive failures is `sse:{tab}:error` emitted.
+
+// Batching: while a reply streams, the sidecar sends hundreds of tiny delta events per
+// second and each one costs a separate IPC round trip + React update. Delta events
+// (COALESCED_EVENTS) arriving within runtimeSettings.sseBatchWindowMs are buffered and
+// emitted together as one `sse:{tab}:batch` payload. Any other event flushes the pending
+// batch first, so ordering is preserved and critical events are never delayed.
+
+/// First reconnect delay
+const SSE_RECONNECT_BASE_DELAY_MS: u64 = 500;
+/// Upper bound for the reconnect delay
+const SSE_RECONNECT_MAX_DELAY_MS: u64 = 30_000;
+/// Consecutive failed attempts before giving up
+const SSE_RECONNECT_MAX_ATTEMPTS: u32 = 10;
+
+/// Delta events that may be held back and coalesced into `sse:{tab}:batch`
+const COALESCED_EVENTS: &[&str] = &[
+    "chat:message-chunk",
+    "chat:thinking-chunk",
+    "chat:tool-input-delta",
+    "chat:tool-result-delta",
+    "chat:subagent-tool-input-delta",
+    "chat:subagent-tool-result-delta",
+];
+/// Plain-text delta whose consecutive payloads can be concatenated
+const MERGEABLE_TEXT_EVENT: &str = "chat:message-chunk";
+/// Flush early once a batch holds this many entries...
+const SSE_BATCH_MAX_ENTRIES: usize = 512;
+/// ...or this many payload bytes
+const SSE_BATCH_MAX_BYTES: usize = 256 * 1024;
+
+/// Events that end the reply in progress
+const MESSAGE_END_EVENTS: &[&str] = &["chat:message-complete", "chat:message-stopped", "chat:message-error"];
+
+/// Recent event messages kept for late subscribers...
+const SSE_REPLAY_MAX_MESSAGES: usize = 256;
+/// ...up to this many payload bytes
+const SSE_REPLAY_MAX_BYTES: usize = 512 * 1024;
+
+/// Payload of `sse:{tab}:reconnecting`
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SseReconnectingEvent {
+    attempt: u32,
+    max_attempts: u32,
+    delay_ms: u64,
+    error: String,
+    last_event_id: Option<String>,
+}
+
+/// Payload of `sse:{tab}:reconnected`
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SseReconnectedEvent {
+    attempts: u32,
+    last_event_id: Option<String>,
+}
+
+/// Payload of `sse:{tab}:stalled`
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SseStalledEvent {
+    /// Time since the last event
+    silent_ms: u64,
+    /// Time since the last heartbeat (None before the first one); recent means the
+    /// sidecar is alive and only the agent is silent
+    last_heartbeat_ms: Option<u64>,
+    /// Heartbeats received so far
+    heartbeats: u64,
+}
+
+/// Liveness of one Tab's stream, with heartbeats and events tracked separately
+struct SseActivity {
+    last_event: Instant,
+    last_heartbeat: Option<Instant>,
+    heartbeats: u64,
+    /// Between the first delta of a reply and its end event
+    message_in_progress: bool,
+    /// The current silence was already reported
+    stall_reported: bool,
+}
+
+impl SseActivity {
+    fn new(now: Instant) -> Self {
+        Self { last_event: now, last_heartbeat: None, heartbeats: 0, message_in_progress: false, stall_reported: false }
+    }
+
+    fn heartbeat(&mut self, now: Instant) {
+        self.last_heartbeat = Some(now);
+        self.heartbeats += 1;
+    }
+
+    fn event(&mut self, name: &str, now: Instant) {
+        self.last_event = now;
+        self.stall_reported = false;
+        if MESSAGE_END_EVENTS.contains(&name) {
+            self.message_in_progress = false;
+        } else if COALESCED_EVENTS.contains(&name) {
+            self.message_in_progress = true;
+        }
+    }
+
+    /// When the reply in progress counts as stalled (None: nothing to watch)
+    fn stall_deadline(&self, stall_after: Option<Duration>) -> Option<Instant> {
+        match stall_after {
+            Some(after) if self.message_in_progress && !self.stall_reported => Some(self.last_event + after),
+            _ => None,
+        }
+    }
+
+    /// Report a stall once per silence
+    fn check_stall(&mut self, now: Instant, stall_after: Option<Duration>) -> Option<SseStalledEvent> {
+        if self.stall_deadline(stall_after)? > now {
+            return None;
+        }
+        self.stall_reported = true;
+        Some(SseStalledEvent {
+            silent_ms: now.duration_since(self.last_event).as_millis() as u64,
+            last_heartbeat_ms: self.last_heartbeat.map(|at| now.duration_since(at).as_millis() as u64),
+            heartbeats: self.heartbeats,
+        })
+    }
+
+    /// "last event 12s ago, last heartbeat 3s ago" for log messages
+    fn describe(&self, now: Instant) -> String {
+        let heartbeat = match self.last_heartbeat {
+            Some(at) => format!("{}s ago", now.duration_since(at).as_secs()),
+            None => "never".to_string(),
+        };
+        format!(
+            "last event {}s ago, last heartbeat {} ({} heartbeats)",
+            now.duration_since(self.last_event).as_secs(), heartbeat, self.heartbeats
+        )
+    }
+}
+
+/// Resume state carried across reconnects of one Tab's stream
+struct SseSession {
+    /// Last `id:` seen (sent back as `Last-Event-ID`)
+    last_event_id: Option<String>,
+    /// Consecutive failed attempts (0 while connected)
+    failed_attempts: u32,
+    /// Server-requested base reconnect delay (`retry:` field)
+    retry_ms: Option<u64>,
+    /// Opt-in debug recording (runtimeSettings.sseRecordingEnabled)
+    recorder: Option<SseRecorder>,
+    /// Heartbeat and event timing (stall detection)
+    activity: SseActivity,
+}
+
+impl Default for SseSession {
+    fn default() -> Self {
+        Self {
+            last_event_id: None,
+            failed_attempts: 0,
+            retry_ms: None,
+            recorder: None,
+            activity: SseActivity::new(Instant::now()),
+        }
+    }
+}
+
+/// One entry of the `sse:{tab}:batch` payload, in stream order
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct SseBatchEntry {
+    event: String,
+    data: String,
+}
+
+/// Message pushed over a Tab's channel; each variant mirrors one global event
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SseMessage {
+    /// `sse:{tab}:{event}` - one SSE event with its raw data
+    Event { event: String, data: String },
+    /// `sse:{tab}:batch` - coalesced delta events in stream order
+    Batch { entries: Vec<SseBatchEntry> },
+    /// `sse:{tab}:reconnecting`
+    Reconnecting(SseReconnectingEvent),
+    /// `sse:{tab}:reconnected`
+    Reconnected(SseReconnectedEvent),
+    /// `sse:{tab}:error` - reconnecting gave up
+    Error { message: String },
+    /// `sse:{tab}:stalled` - no events for a while during a reply
+    Stalled(SseStalledEvent),
+}
+
+impl SseMessage {
+    /// Payload size of stream content; None for connection status messages (not replayed)
+    fn replay_bytes(&self) -> Option<usize> {
+        match self {
+            SseMessage::Event { event, data } => Some(event.len() + data.len()),
+            SseMessage::Batch { entries } => Some(entries.iter().map(|e| e.event.len() + e.data.len()).sum()),
+            _ => None,
+        }
+    }
+
+    /// Name of the equivalent global event
+    fn global_event_name(&self, tab_id: &str) -> String {
+        let suffix = match self {
+            SseMessage::Event { event, .. } => event.as_str(),
+            SseMessage::Batch { .. } => "batch",
+            SseMessage::Reconnecting(_) => "reconnecting",
+            SseMessage::Reconnected(_) => "reconnected",
+            SseMessage::Error { .. } => "error",
+            SseMessage::Stalled(_) => "stalled",
+        };
+        format!("sse:{}:{}", tab_id, suffix)
+    }
+}
+
+/// Where a Tab's stream is delivered
+#[derive(Clone)]
+pub(crate) enum SseSink {
+    /// App-wide `sse:{tab}:{event}` events (fallback)
+    Global,
+    /// Ordered messages to the webview that opened the stream
+    Channel(Channel<SseMessage>),
+}
 
-/// Single SSE connection for a Tab
+/// Delivers one Tab's messages to one sink
+struct SseOutput {
+    app: AppHandle,
+    tab_id: String,
+    sink: SseSink,
+    /// Set once the channel can no longer deliver (its webview is gone)
+    closed: AtomicBool,
+}
+
+impl SseOutput {
+    fn new(app: AppHandle, tab_id: &str, sink: SseSink) -> Self {
+        Self { app, tab_id: tab_id.to_string(), sink, closed: AtomicBool::new(false) }
+    }
+
+    /// True after a channel send failed; the stream has no consumer left
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    fn send(&self, message: SseMessage) {
+        let result = match &self.sink {
+            SseSink::Channel(channel) => {
+                if self.is_closed() {
+                    return;
+                }
+                channel.send(message).map_err(|e| {
+                    self.closed.store(true, Ordering::SeqCst);
+                    format!("channel {} closed: {}", channel.id(), e)
+                })
+            }
+            SseSink::Global => {
+                let name = message.global_event_name(&self.tab_id);
+                let emitted = match message {
+                    SseMessage::Event { data, .. } => self.app.emit(&name, data),
+                    SseMessage::Batch { entries } => self.app.emit(&name, entries),
+                    SseMessage::Reconnecting(payload) => self.app.emit(&name, payload),
+                    SseMessage::Reconnected(payload) => self.app.emit(&name, payload),
+                    SseMessage::Error { message } => self.app.emit(&name, message),
+                    SseMessage::Stalled(payload) => self.app.emit(&name, payload),
+                };
+                emitted.map_err(|e| format!("failed to emit {}: {}", name, e))
+            }
+        };
+        if let Err(e) = result {
+            crate::logger::error(&self.app, format!("[sse-proxy] Tab {} {}", self.tab_id, e));
+        }
+    }
+}
+
+/// Most recent event messages of a stream, bounded by count and bytes
+#[derive(Default)]
+struct SseReplayBuffer {
+    messages: VecDeque<(SseMessage, usize)>,
+    bytes: usize,
+    /// Stream URL the buffered messages came from
+    source: Option<String>,
+}
+
+impl SseReplayBuffer {
+    /// Follow the stream to `url`; messages from another sidecar (a restart on a new
+    /// port) belong to a finished session and are dropped. Returns whether any were.
+    fn retarget(&mut self, url: &str) -> bool {
+        if self.source.as_deref() == Some(url) {
+            return false;
+        }
+        self.source = Some(url.to_string());
+        let stale = !self.messages.is_empty();
+        self.messages.clear();
+        self.bytes = 0;
+        stale
+    }
+
+    /// Keep `message` if it is stream content, evicting the oldest beyond the bounds
+    fn push(&mut self, message: &SseMessage) {
+        let Some(size) = message.replay_bytes() else {
+            return;
+        };
+        self.messages.push_back((message.clone(), size));
+        self.bytes += size;
+        while self.messages.len() > SSE_REPLAY_MAX_MESSAGES
+            || (self.bytes > SSE_REPLAY_MAX_BYTES && self.messages.len() > 1)
+        {
+            if let Some((_, evicted)) = self.messages.pop_front() {
+                self.bytes -= evicted;
+            }
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &SseMessage> {
+        self.messages.iter().map(|(message, _)| message)
+    }
+}
+
+struct SseHubState {
+    next_id: u64,
+    /// Subscribers in subscription order
+    subscribers: Vec<(u64, SseOutput)>,
+    replay: SseReplayBuffer,
+}
+
+/// Fans one upstream stream out to every subscriber of a Tab
+pub(crate) struct SseHub {
+    app: AppHandle,
+    tab_id: String,
+    state: std::sync::Mutex<SseHubState>,
+    /// Follows the Tab's sidecar: stays open without subscribers
+    bound: AtomicBool,
+}
+
+impl SseHub {
+    fn new(app: AppHandle, tab_id: &str) -> Self {
+        Self {
+            app,
+            tab_id: tab_id.to_string(),
+            state: std::sync::Mutex::new(SseHubState {
+                next_id: 1,
+                subscribers: Vec::new(),
+                replay: SseReplayBuffer::default(),
+            }),
+            bound: AtomicBool::new(false),
+        }
+    }
+
+    fn is_bound(&self) -> bool {
+        self.bound.load(Ordering::SeqCst)
+    }
+
+    fn subscriber_count(&self) -> usize {
+        self.state.lock().map(|state| state.subscribers.len()).unwrap_or(0)
+    }
+
+    /// Add a subscriber, which first receives the replay buffer
+    /// Returns (subscriber id, replayed messages). Global events reach every window
+    /// anyway, so a Global sink joins the existing global subscriber if there is one.
+    fn subscribe(&self, sink: SseSink) -> (u64, usize) {
+        let Ok(mut state) = self.state.lock() else {
+            return (0, 0);
+        };
+        if matches!(sink, SseSink::Global) {
+            if let Some((id, _)) = state.subscribers.iter().find(|(_, o)| matches!(o.sink, SseSink::Global)) {
+                return (*id, 0);
+            }
+        }
+
+        // Under the lock, so no live message can slip in before the replay
+        let output = SseOutput::new(self.app.clone(), &self.tab_id, sink);
+        let mut replayed = 0;
+        for message in state.replay.iter() {
+            output.send(message.clone());
+            replayed += 1;
+        }
+        let id = state.next_id;
+        state.next_id += 1;
+        state.subscribers.push((id, output));
+        (id, replayed)
+    }
+
+    /// Point the hub at a new upstream `url` (before its connection starts)
+    fn retarget(&self, url: &str) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if state.replay.retarget(url) {
+            log::info!("[sse-proxy] Tab {} cleared replay buffer of its previous stream", self.tab_id);
+        }
+    }
+
+    /// Remove a subscriber; returns how many remain
+    fn unsubscribe(&self, id: u64) -> usize {
+        let Ok(mut state) = self.state.lock() else {
+            return 0;
+        };
+        state.subscribers.retain(|(subscriber, _)| *subscriber != id);
+        state.subscribers.len()
+    }
+
+    /// No subscriber left and not bound to a sidecar: the upstream connection should close
+    fn is_idle(&self) -> bool {
+        !self.is_bound() && self.subscriber_count() == 0
+    }
+
+    /// Send `message` to every subscriber, dropping those whose channel closed
+    pub(crate) fn broadcast(&self, message: SseMessage) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        state.replay.push(&message);
+        let Some(((_, last), others)) = state.subscribers.split_last() else {
+            return;
+        };
+        for (_, output) in others {
+            output.send(message.clone());
+        }
+        last.send(message);
+
+        let before = state.subscribers.len();
+        state.subscribers.retain(|(_, output)| !output.is_closed());
+        if state.subscribers.len() < before {
+            log::info!(
+                "[sse-proxy] Tab {} dropped {} closed subscriber(s), {} left",
+                self.tab_id, before - state.subscribers.len(), state.subscribers.len()
+            );
+        }
+    }
+}
+
+/// Buffers delta events for up to `window` after the first one arrives
+struct SseBatcher {
+    window: std::time::Duration,
+    entries: Vec<SseBatchEntry>,
+    bytes: usize,
+    deadline: Option<tokio::time::Instant>,
+}
+
+impl SseBatcher {
+    fn new(window_ms: u64) -> Self {
+        Self {
+            window: std::time::Duration::from_millis(window_ms),
+            entries: Vec::new(),
+            bytes: 0,
+            deadline: None,
+        }
+    }
+
+    /// Whether `event` should go through the batch (false when batching is disabled)
+    fn accepts(&self, event: &str) -> bool {
+        !self.window.is_zero() && COALESCED_EVENTS.contains(&event)
+    }
+
+    /// Buffer a delta event; returns true if the batch is full and must be flushed now
+    fn push(&mut self, event: String, data: String) -> bool {
+        self.bytes += data.len();
+        match self.entries.last_mut() {
+            Some(last) if last.event == event && event == MERGEABLE_TEXT_EVENT => {
+                last.data.push_str(&data);
+            }
+            _ => self.entries.push(SseBatchEntry { event, data }),
+        }
+        if self.deadline.is_none() {
+            self.deadline = Some(tokio::time::Instant::now() + self.window);
+        }
+        self.entries.len() >= SSE_BATCH_MAX_ENTRIES || self.bytes >= SSE_BATCH_MAX_BYTES
+    }
+
+    /// When the pending batch must be flushed (None while empty)
+    fn deadline(&self) -> Option<tokio::time::Instant> {
+        self.deadline
+    }
+
+    /// Take the pending entries and reset the window
+    fn take(&mut self) -> Vec<SseBatchEntry> {
+        self.bytes = 0;
+        self.deadline = None;
+        std::mem::take(&mut self.entries)
+    }
+}
+
+/// Single upstream SSE connection for a Tab
 struct SseConnection {
     /// Shared running flag - used to gracefully stop the SSE stream
     running: Arc<AtomicBool>,
     /// Task handle for aborting if graceful stop fails
     abort_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Sidecar stream URL
+    url: String,
+    /// Subscribers of this Tab's stream
+    hub: Arc<SseHub>,
 }
 
 impl SseConnection {
-    fn new() -> Self {
+    fn new(url: &str, hub: Arc<SseHub>) -> Self {
         Self {
             running: Arc::new(AtomicBool::new(false)),
             abort_handle: None,
+            url: url.to_string(),
+            hub,
         }
     }
     
@@ -54,82 +567,267 @@ impl SseConnection {
 pub struct SseProxyState {
     /// Tab ID -> SSE connection
     connections: Mutex<HashMap<String, SseConnection>>,
+    /// Tab ID -> hub fed by recording replays (no upstream connection)
+    replays: Mutex<HashMap<String, Arc<SseHub>>>,
 }
 
 impl Default for SseProxyState {
     fn default() -> Self {
         Self {
             connections: Mutex::new(HashMap::new()),
+            replays: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Replay hub of a Tab, created on first use; kept until stop_sse_proxy with `replay`
+pub(crate) async fn replay_hub(app: &AppHandle, state: &SseProxyState, tab_id: &str) -> Arc<SseHub> {
+    let mut replays = state.replays.lock().await;
+    let hub = replays
+        .entry(tab_id.to_string())
+        .or_insert_with(|| Arc::new(SseHub::new(app.clone(), tab_id)));
+    Arc::clone(hub)
+}
+
+/// Stream URL of the sidecar listening on `port`
+fn sidecar_stream_url(port: u16) -> String {
+    format!("http://127.0.0.1:{}/chat/stream", port)
+}
+
+/// Start the upstream task for `url`, delivering to `hub`
+fn spawn_connection(app: &AppHandle, tab_id: &str, url: &str, hub: Arc<SseHub>) -> SseConnection {
+    // Create new connection with shared running flag
+    let mut conn = SseConnection::new(url, Arc::clone(&hub));
+    conn.running.store(true, Ordering::SeqCst);
+    
+    let app_handle = app.clone();
+    let tab_id = tab_id.to_string();
+    let url = url.to_string();
+    // Share the same running flag with the spawned task
+    let running = conn.running.clone();
+    
+    // Spawn async task to handle SSE stream (reconnects on its own)
+    let handle = tokio::spawn(async move {
+        run_sse(&app_handle, &url, &running, &tab_id, &hub).await;
+        running.store(false, Ordering::SeqCst);
+    });
+    
+    conn.abort_handle = Some(handle);
+    conn
+}
+
+/// Point a Tab's stream at its sidecar on `port`, keeping the hub and its subscribers
+fn attach_locked(
+    app: &AppHandle,
+    connections: &mut HashMap<String, SseConnection>,
+    tab_id: &str,
+    port: u16,
+) -> Arc<SseHub> {
+    let url = sidecar_stream_url(port);
+    if let Some(conn) = connections.get(tab_id) {
+        if conn.running.load(Ordering::SeqCst) && conn.url == url {
+            conn.hub.bound.store(true, Ordering::SeqCst);
+            return Arc::clone(&conn.hub);
         }
     }
+
+    let hub = match connections.remove(tab_id) {
+        Some(mut conn) => {
+            conn.stop();
+            conn.hub
+        }
+        None => Arc::new(SseHub::new(app.clone(), tab_id)),
+    };
+    hub.bound.store(true, Ordering::SeqCst);
+    hub.retarget(&url);
+    log::info!(
+        "[sse-proxy] Tab {} attached to sidecar on port {} ({} subscriber(s))",
+        tab_id, port, hub.subscriber_count()
+    );
+    connections.insert(tab_id.to_string(), spawn_connection(app, tab_id, &url, Arc::clone(&hub)));
+    hub
+}
+
+/// Tear down a Tab's upstream connection after its sidecar on `port` stopped
+/// Subscribers stay on the hub so a restarted sidecar reattaches to them.
+fn detach_locked(connections: &mut HashMap<String, SseConnection>, tab_id: &str, port: u16) {
+    let Some(conn) = connections.get_mut(tab_id) else {
+        return;
+    };
+    // Already retargeted to a newer sidecar
+    if conn.url != sidecar_stream_url(port) {
+        return;
+    }
+    conn.stop();
+    let subscribers = conn.hub.subscriber_count();
+    if subscribers == 0 {
+        connections.remove(tab_id);
+    }
+    log::info!(
+        "[sse-proxy] Tab {} detached from stopped sidecar on port {} ({} subscriber(s) waiting)",
+        tab_id, port, subscribers
+    );
+}
+
+/// Port of the Tab's running, healthy sidecar
+fn healthy_sidecar_port(app: &AppHandle, tab_id: &str) -> Option<u16> {
+    let manager = app.state::<ManagedSidecarManager>();
+    let manager = manager.lock().ok()?;
+    manager.get_instance(tab_id).filter(|instance| instance.healthy).map(|instance| instance.port)
 }
 
-/// Start SSE proxy connection for a specific Tab
+/// Attach and detach Tab streams as their sidecars become healthy or stop
+/// Lifecycle events are handled in order by a single task.
+pub fn bind_to_sidecars(app: &AppHandle, state: Arc<SseProxyState>, manager: &ManagedSidecarManager) {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<SidecarLifecycleEvent>();
+    match manager.lock() {
+        Ok(mut manager) => manager.add_lifecycle_listener(Box::new(move |event| {
+            let _ = tx.send(event.clone());
+        })),
+        Err(e) => {
+            log::error!("[sse-proxy] Failed to bind to sidecar lifecycle: {}", e);
+            return;
+        }
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            let mut connections = state.connections.lock().await;
+            match event {
+                // The global sidecar (Settings page) has no chat stream to follow
+                SidecarLifecycleEvent::Healthy { tab_id, .. } | SidecarLifecycleEvent::Stopped { tab_id, .. }
+                    if tab_id == GLOBAL_SIDECAR_ID => {}
+                SidecarLifecycleEvent::Healthy { tab_id, port } => {
+                    attach_locked(&app, &mut connections, &tab_id, port);
+                }
+                SidecarLifecycleEvent::Stopped { tab_id, port } => {
+                    detach_locked(&mut connections, &tab_id, port);
+                }
+            }
+        }
+    });
+}
+
+/// Subscribe to a Tab's SSE stream
+///
+/// Without `url` the stream follows the Tab's sidecar (attached now if needed, and
+/// reattached across sidecar restarts). With `url` a connection to that URL is opened
+/// if the Tab isn't streaming from it yet (legacy). With `replay` the Tab's replay hub
+/// is joined instead (see replay_sse_recording); no sidecar is needed.
+/// With `channel`, messages go only to the calling webview; without it, events are
+/// emitted globally. Returns the subscriber id for stop_sse_proxy.
 #[tauri::command]
 pub async fn start_sse_proxy(
     app: AppHandle,
+    webview: tauri::Webview,
     state: tauri::State<'_, Arc<SseProxyState>>,
-    url: String,
+    url: Option<String>,
     tab_id: Option<String>,
-) -> Result<(), String> {
+    channel: Option<JavaScriptChannelId>,
+    replay: Option<bool>,
+) -> Result<u64, String> {
+    // Option<Channel> is not a command argument; resolve the id against the caller
+    let channel: Option<Channel<SseMessage>> = channel.map(|id| id.channel_on(webview));
     let tab_id = tab_id.unwrap_or_else(|| "__default__".to_string());
+    let delivery = if channel.is_some() { "channel" } else { "global events" };
+    let sink = channel.map_or(SseSink::Global, SseSink::Channel);
+
+    if replay.unwrap_or(false) {
+        let (id, replayed) = replay_hub(&app, &state, &tab_id).await.subscribe(sink);
+        log::info!(
+            "[sse-proxy] Tab {} replay subscriber {} joined ({}), {} message(s) replayed",
+            tab_id, id, delivery, replayed
+        );
+        return Ok(id);
+    }
     
     let mut connections = state.connections.lock().await;
+
+    let Some(url) = url else {
+        // Tab-centric: join the sidecar-bound stream (also while a restart is pending)
+        let hub = match connections.get(&tab_id).filter(|conn| conn.hub.is_bound()) {
+            Some(conn) => Arc::clone(&conn.hub),
+            None => {
+                let port = healthy_sidecar_port(&app, &tab_id)
+                    .ok_or_else(|| format!("[sse-proxy] No running sidecar for tab {}", tab_id))?;
+                attach_locked(&app, &mut connections, &tab_id, port)
+            }
+        };
+        let (id, replayed) = hub.subscribe(sink);
+        log::info!(
+            "[sse-proxy] Tab {} subscriber {} joined ({}), {} message(s) replayed",
+            tab_id, id, delivery, replayed
+        );
+        return Ok(id);
+    };
     
-    // Check if already running for this tab
+    // Join the Tab's running stream
     if let Some(conn) = connections.get(&tab_id) {
-        if conn.running.load(Ordering::SeqCst) {
-            log::debug!("[sse-proxy] Tab {} already has an active connection", tab_id);
-            return Ok(());
+        if conn.running.load(Ordering::SeqCst) && conn.url == url {
+            let (id, replayed) = conn.hub.subscribe(sink);
+            log::info!(
+                "[sse-proxy] Tab {} subscriber {} joined ({}), {} message(s) replayed",
+                tab_id, id, delivery, replayed
+            );
+            return Ok(id);
         }
     }
     
-    // Stop existing connection if any
-    if let Some(mut conn) = connections.remove(&tab_id) {
-        conn.stop();
-    }
-    
-    // Create new connection with shared running flag
-    let mut conn = SseConnection::new();
-    conn.running.store(true, Ordering::SeqCst);
-    
-    let app_handle = app.clone();
-    let tab_id_clone = tab_id.clone();
-    // Share the same running flag with the spawned task
-    let running = conn.running.clone();
-    
-    // Spawn async task to handle SSE stream
-    let handle = tokio::spawn(async move {
-        match connect_sse(&app_handle, &url, &running, &tab_id_clone).await {
-            Ok(_) => {
-                log::debug!("[sse-proxy] Tab {} connection closed normally", tab_id_clone);
-            }
-            Err(e) => {
-                log::error!("[sse-proxy] Tab {} connection error: {}", tab_id_clone, e);
-                // Emit error with tab_id prefix so frontend can filter
-                let _ = app_handle.emit(&format!("sse:{}:error", tab_id_clone), e.to_string());
-            }
+    // Stop existing connection if any; a URL change (sidecar restarted on another
+    // port) keeps the current subscribers
+    let hub = match connections.remove(&tab_id) {
+        Some(mut conn) => {
+            conn.stop();
+            if conn.hub.is_idle() { Arc::new(SseHub::new(app.clone(), &tab_id)) } else { conn.hub }
         }
-    });
-    
-    conn.abort_handle = Some(handle);
-    connections.insert(tab_id.clone(), conn);
+        None => Arc::new(SseHub::new(app.clone(), &tab_id)),
+    };
+    hub.retarget(&url);
+    let (id, _) = hub.subscribe(sink);
+    connections.insert(tab_id.clone(), spawn_connection(&app, &tab_id, &url, hub));
     
-    log::info!("[sse-proxy] Started connection for tab {}", tab_id);
+    log::info!("[sse-proxy] Started connection for tab {} (subscriber {}, {})", tab_id, id, delivery);
     
-    Ok(())
+    Ok(id)
 }
 
-/// Stop SSE proxy connection for a specific Tab
+/// Unsubscribe from a Tab's SSE stream; the connection stops with its last subscriber
+/// unless it is bound to the Tab's running sidecar.
+/// Without `subscriber_id` the connection is stopped for all subscribers.
+/// With `replay` the Tab's replay hub is left instead (dropped with its last subscriber).
 #[tauri::command]
 pub async fn stop_sse_proxy(
     state: tauri::State<'_, Arc<SseProxyState>>,
     tab_id: Option<String>,
+    subscriber_id: Option<u64>,
+    replay: Option<bool>,
 ) -> Result<(), String> {
     let tab_id = tab_id.unwrap_or_else(|| "__default__".to_string());
+
+    if replay.unwrap_or(false) {
+        let mut replays = state.replays.lock().await;
+        let remaining = match (subscriber_id, replays.get(&tab_id)) {
+            (Some(id), Some(hub)) => hub.unsubscribe(id),
+            _ => 0,
+        };
+        if remaining == 0 && replays.remove(&tab_id).is_some() {
+            log::info!("[sse-proxy] Dropped replay stream of tab {}", tab_id);
+        }
+        return Ok(());
+    }
     
     let mut connections = state.connections.lock().await;
     
+    if let (Some(id), Some(conn)) = (subscriber_id, connections.get(&tab_id)) {
+        let remaining = conn.hub.unsubscribe(id);
+        let keep_bound = conn.hub.is_bound() && conn.running.load(Ordering::SeqCst);
+        if remaining > 0 || keep_bound {
+            log::info!("[sse-proxy] Tab {} subscriber {} left, {} remaining", tab_id, id, remaining);
+            return Ok(());
+        }
+    }
+    
     if let Some(mut conn) = connections.remove(&tab_id) {
         conn.stop();
         log::info!("[sse-proxy] Stopped connection for tab {}", tab_id);
@@ -149,46 +847,123 @@ pub async fn stop_all_sse_proxies(
         conn.stop();
         log::info!("[sse-proxy] Stopped connection for tab {}", tab_id);
     }
+    state.replays.lock().await.clear();
 
     Ok(())
 }
 
+/// Keep a Tab's SSE stream alive: reconnect with jittered backoff until stopped or
+/// SSE_RECONNECT_MAX_ATTEMPTS consecutive attempts fail
+async fn run_sse(app: &AppHandle, url: &str, running: &AtomicBool, tab_id: &str, hub: &SseHub) {
+    let mut session = SseSession::default();
+    if app_config::runtime_settings().sse_recording_enabled {
+        match SseRecorder::create(tab_id, url) {
+            Ok(recorder) => {
+                crate::logger::info(app, format!(
+                    "[sse-proxy] Tab {} recording to {}",
+                    tab_id, recorder.path().display()
+                ));
+                session.recorder = Some(recorder);
+            }
+            Err(e) => crate::logger::warn(app, e),
+        }
+    }
+
+    while running.load(Ordering::SeqCst) && !hub.is_idle() {
+        let error = match connect_sse(app, url, running, tab_id, hub, &mut session).await {
+            Ok(()) if !running.load(Ordering::SeqCst) || hub.is_idle() => break,
+            // Server closed the stream (e.g. sidecar restart): reconnect like EventSource does
+            Ok(()) => "stream closed by server".to_string(),
+            Err(e) => e.to_string(),
+        };
+        if let Some(recorder) = &session.recorder {
+            recorder.note(&format!("disconnected: {}", error));
+        }
+
+        session.failed_attempts += 1;
+        if session.failed_attempts > SSE_RECONNECT_MAX_ATTEMPTS {
+            log::error!(
+                "[sse-proxy] Tab {} giving up after {} reconnect attempts: {}",
+                tab_id, SSE_RECONNECT_MAX_ATTEMPTS, error
+            );
+            hub.broadcast(SseMessage::Error { message: error });
+            return;
+        }
+
+        let base_ms = session.retry_ms.unwrap_or(SSE_RECONNECT_BASE_DELAY_MS);
+        let delay_ms = reconnect_delay_ms(session.failed_attempts, base_ms, random_unit());
+        log::warn!(
+            "[sse-proxy] Tab {} reconnecting in {}ms (attempt {}/{}): {}",
+            tab_id, delay_ms, session.failed_attempts, SSE_RECONNECT_MAX_ATTEMPTS, error
+        );
+        hub.broadcast(SseMessage::Reconnecting(SseReconnectingEvent {
+            attempt: session.failed_attempts,
+            max_attempts: SSE_RECONNECT_MAX_ATTEMPTS,
+            delay_ms,
+            error,
+            last_event_id: session.last_event_id.clone(),
+        }));
+        tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+    }
+
+    log::debug!("[sse-proxy] Tab {} connection closed normally", tab_id);
+}
+
+/// Backoff for the n-th consecutive attempt (1-based): exponential from `base_ms`, capped,
+/// with "equal jitter" (half fixed, half random) so Tabs don't reconnect in lockstep
+/// `unit` is a random value in [0, 1)
+fn reconnect_delay_ms(attempt: u32, base_ms: u64, unit: f64) -> u64 {
+    let exp = base_ms.saturating_mul(1u64 << attempt.saturating_sub(1).min(16));
+    let capped = exp.min(SSE_RECONNECT_MAX_DELAY_MS);
+    let half = capped / 2;
+    half + (half as f64 * unit.clamp(0.0, 1.0)) as u64
+}
+
+/// Random value in [0, 1) (uuid v4 is already a dependency and uses the OS RNG)
+fn random_unit() -> f64 {
+    (uuid::Uuid::new_v4().as_u128() as u64 >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Send the pending batch; a single entry is sent as a plain event
+fn flush_sse_batch(hub: &SseHub, batcher: &mut SseBatcher) {
+    let mut entries = batcher.take();
+    match entries.len() {
+        0 => {}
+        1 => {
+            let entry = entries.remove(0);
+            hub.broadcast(SseMessage::Event { event: entry.event, data: entry.data });
+        }
+        _ => hub.broadcast(SseMessage::Batch { entries }),
+    }
+}
+
 /// Connect to SSE endpoint and forward events with Tab prefix
+/// Returns Ok when the stream ends or `running` is cleared, Err on connection/stream errors
 async fn connect_sse(
     app: &AppHandle, 
     url: &str,
     running: &AtomicBool,
     tab_id: &str,
+    hub: &SseHub,
+    session: &mut SseSession,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     use futures_util::StreamExt;
     use crate::logger;
 
     logger::info(app, format!("[sse-proxy] Tab {} connecting to {}", tab_id, url));
 
-    // Build client with read_timeout (idle timeout) for SSE long connections
-    // IMPORTANT: Do NOT use timeout() which is total request time - SSE connections are meant to be long-lived
-    // Use read_timeout instead: if no data received within this time, connection is considered dead
-    // Backend sends heartbeat every 15s, so 60s read_timeout gives 4x margin
-    // CRITICAL: Enable tcp_nodelay to disable Nagle's algorithm for immediate packet transmission
-    // Without this, small SSE events may be buffered and delayed, causing UI to feel unresponsive
-    // Force HTTP/1.1 for compatibility with Bun server (HTTP/2 may cause connection issues on Windows)
-    // Use short-lived connection pool to balance performance and stability
-    // CRITICAL: Disable proxy for localhost - reqwest uses system proxy by default!
-    let client = reqwest::Client::builder()
-        .read_timeout(std::time::Duration::from_secs(SSE_READ_TIMEOUT_SECS))
-        .tcp_nodelay(true)
-        .http1_only()  // Force HTTP/1.1 for stability (TODO v0.1.8: test HTTP/2 negotiation)
-        .pool_idle_timeout(std::time::Duration::from_secs(5))  // Recycle idle connections after 5s
-        .pool_max_idle_per_host(2)  // Keep up to 2 connections for reuse
-        .no_proxy()  // Disable proxy for all requests (especially localhost)
-        .build()
-        .map_err(|e| format!("[sse-proxy] Failed to create HTTP client: {}", e))?;
-    
-    let response = client
-        .get(url)
-        .header("Accept", "text/event-stream")
-        .send()
-        .await?;
+    let runtime = app_config::runtime_settings();
+    let read_timeout_secs = runtime.sse_read_timeout_secs;
+
+    // Shared SSE client with read_timeout (idle timeout) for long connections
+    // (see http_clients.rs for why timeout() must not be used here)
+    let client = http_clients::client(app, ClientPurpose::Sse)?;
+
+    let mut request = client.get(url).header("Accept", "text/event-stream");
+    if let Some(last_event_id) = &session.last_event_id {
+        request = request.header("Last-Event-ID", last_event_id);
+    }
+    let response = http_clients::send(app, ClientPurpose::Sse, request).await?;
 
     if !response.status().is_success() {
         let err = format!("[sse-proxy] Tab {} connection failed: {}", tab_id, response.status());
@@ -198,19 +973,60 @@ async fn connect_sse(
 
     logger::info(app, format!(
         "[sse-proxy] Tab {} connected, status: {}, read_timeout: {}s (heartbeat interval: 15s)",
-        tab_id, response.status(), SSE_READ_TIMEOUT_SECS
+        tab_id, response.status(), read_timeout_secs
     ));
 
+    if let Some(recorder) = &session.recorder {
+        recorder.note_connected(&format!("{} (Last-Event-ID: {:?})", response.status(), session.last_event_id));
+    }
+
+    if session.failed_attempts > 0 {
+        logger::info(app, format!(
+            "[sse-proxy] Tab {} reconnected after {} attempt(s), resuming from {:?}",
+            tab_id, session.failed_attempts, session.last_event_id
+        ));
+        hub.broadcast(SseMessage::Reconnected(SseReconnectedEvent {
+            attempts: session.failed_attempts,
+            last_event_id: session.last_event_id.clone(),
+        }));
+        session.failed_attempts = 0;
+    }
+
     let mut stream = response.bytes_stream();
-    let mut buffer = String::new();
+    // Byte-level decoder: multi-byte UTF-8 split across chunks stays intact
+    let mut decoder = SseDecoder::new();
+    let mut batcher = SseBatcher::new(runtime.sse_batch_window_ms);
+    let stall_after = (runtime.sse_stall_timeout_secs > 0).then(|| Duration::from_secs(runtime.sse_stall_timeout_secs));
     let mut chunk_count: u64 = 0;
+    let heartbeats_before = session.activity.heartbeats;
 
-    while running.load(Ordering::SeqCst) {
-        match stream.next().await {
+    while running.load(Ordering::SeqCst) && !hub.is_idle() {
+        // Wake up for a pending batch or a possible stall even if no data arrives
+        let wake_at = [batcher.deadline(), session.activity.stall_deadline(stall_after)].into_iter().flatten().min();
+        let next = match wake_at {
+            Some(deadline) => match tokio::time::timeout_at(deadline, stream.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    let now = Instant::now();
+                    if batcher.deadline().is_some_and(|deadline| deadline <= now) {
+                        flush_sse_batch(hub, &mut batcher);
+                    }
+                    if let Some(stalled) = session.activity.check_stall(now, stall_after) {
+                        logger::warn(app, format!(
+                            "[sse-proxy] Tab {} reply stalled: {}",
+                            tab_id, session.activity.describe(now)
+                        ));
+                        hub.broadcast(SseMessage::Stalled(stalled));
+                    }
+                    continue;
+                }
+            },
+            None => stream.next().await,
+        };
+        match next {
             Some(Ok(chunk)) => {
                 chunk_count += 1;
-                let text = String::from_utf8_lossy(&chunk);
-                
+
                 // Debug log for first few chunks and periodically
                 if chunk_count <= 3 || chunk_count % 100 == 0 {
                     logger::debug(app, format!(
@@ -218,46 +1034,59 @@ async fn connect_sse(
                         tab_id, chunk_count, chunk.len()
                     ));
                 }
-                
-                buffer.push_str(&text);
-
-                // Process complete SSE events (end with \n\n)
-                while let Some(pos) = buffer.find("\n\n") {
-                    let event_str = buffer[..pos].to_string();
-                    buffer = buffer[pos + 2..].to_string();
-
-                    // Parse and emit SSE event with Tab prefix
-                    if let Some((event_name, data)) = parse_sse_event(&event_str) {
-                        // Log critical state-changing events
-                        if event_name == "chat:message-complete" || event_name == "chat:message-stopped" || event_name == "chat:message-error" {
-                            logger::info(app, format!(
-                                "[sse-proxy] Tab {} emitting critical event: {}",
-                                tab_id, event_name
-                            ));
+
+                if let Some(recorder) = &session.recorder {
+                    recorder.record_chunk(&chunk);
+                }
+                for item in decoder.feed(&chunk) {
+                    let event = match item {
+                        SseItem::Event(event) => event,
+                        SseItem::Retry(millis) => {
+                            session.retry_ms = Some(millis);
+                            continue;
+                        }
+                        // The sidecar's keep-alive (`: ping`), not agent output
+                        SseItem::Comment(_) => {
+                            session.activity.heartbeat(Instant::now());
+                            continue;
                         }
-                        // Emit with tab_id prefix: sse:tab_id:event_name
-                        let prefixed_event = format!("sse:{}:{}", tab_id, event_name);
-                        if let Err(e) = app.emit(&prefixed_event, data) {
-                            logger::error(app, format!(
-                                "[sse-proxy] Tab {} failed to emit {}: {}",
-                                tab_id, prefixed_event, e
-                            ));
+                    };
+                    if event.id.is_some() {
+                        session.last_event_id = event.id;
+                    }
+                    let event_name = event.event;
+                    session.activity.event(&event_name, Instant::now());
+                    if batcher.accepts(&event_name) {
+                        if batcher.push(event_name, event.data) {
+                            flush_sse_batch(hub, &mut batcher);
                         }
+                        continue;
+                    }
+                    // Everything buffered so far goes out before this event
+                    flush_sse_batch(hub, &mut batcher);
+                    // Log critical state-changing events
+                    if event_name == "chat:message-complete" || event_name == "chat:message-stopped" || event_name == "chat:message-error" {
+                        logger::info(app, format!(
+                            "[sse-proxy] Tab {} emitting critical event: {}",
+                            tab_id, event_name
+                        ));
+                    }
+                    hub.broadcast(SseMessage::Event { event: event_name, data: event.data });
+                }
+                // An id-only event updates the resume point without dispatching
+                if let Some(id) = decoder.last_event_id() {
+                    if session.last_event_id.as_deref() != Some(id) {
+                        session.last_event_id = Some(id.to_string());
                     }
                 }
             }
             Some(Err(e)) => {
+                flush_sse_batch(hub, &mut batcher);
                 // Log detailed error information for debugging
                 let err_detail = format!("{:?}", e); // Debug format shows more details
-                let buffer_preview = if buffer.len() > 200 {
-                    format!("{}...(truncated, total {} bytes)", &buffer[..200], buffer.len())
-                } else {
-                    buffer.clone()
-                };
-
                 logger::error(app, format!(
-                    "[sse-proxy] Tab {} stream error after {} chunks\n  Error: {}\n  Error detail: {}\n  Buffer preview: {:?}",
-                    tab_id, chunk_count, e, err_detail, buffer_preview
+                    "[sse-proxy] Tab {} stream error after {} chunks\n  Error: {}\n  Error detail: {}\n  Pending bytes: {}\n  Activity: {}",
+                    tab_id, chunk_count, e, err_detail, decoder.pending_bytes(), session.activity.describe(Instant::now())
                 ));
 
                 let err = format!("[sse-proxy] Tab {} stream error after {} chunks: {}", tab_id, chunk_count, e);
@@ -270,192 +1099,190 @@ async fn connect_sse(
         }
     }
 
-    logger::info(app, format!("[sse-proxy] Tab {} connection closed, processed {} chunks", tab_id, chunk_count));
+    flush_sse_batch(hub, &mut batcher);
+    logger::info(app, format!(
+        "[sse-proxy] Tab {} connection closed, processed {} chunks ({} heartbeats)",
+        tab_id, chunk_count, session.activity.heartbeats - heartbeats_before
+    ));
     Ok(())
 }
 
-/// Parse SSE event format
-/// Per SSE spec, the format is:
-/// - "event: name\n" (event type)
-/// - "data: value\n" (data, can have multiple lines)
-/// - "\n" (empty line ends the event)
-/// IMPORTANT: Per spec, only ONE space after the colon should be skipped (if present)
-fn parse_sse_event(event_str: &str) -> Option<(String, String)> {
-    let mut event_name = String::from("message");
-    let mut data_lines = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for line in event_str.lines() {
-        if line.starts_with("event:") {
-            // Event name can be trimmed
-            event_name = line[6..].trim().to_string();
-        } else if line.starts_with("data:") {
-            // Per SSE spec: skip exactly one space after "data:" if present
-            let content = &line[5..];
-            let data_value = content.strip_prefix(' ').unwrap_or(content);
-            data_lines.push(data_value.to_string());
+    #[test]
+    fn test_reconnect_delay_is_capped_and_jittered() {
+        assert_eq!(reconnect_delay_ms(1, SSE_RECONNECT_BASE_DELAY_MS, 0.0), SSE_RECONNECT_BASE_DELAY_MS / 2);
+        assert_eq!(reconnect_delay_ms(1, SSE_RECONNECT_BASE_DELAY_MS, 1.0), SSE_RECONNECT_BASE_DELAY_MS);
+        assert_eq!(reconnect_delay_ms(3, SSE_RECONNECT_BASE_DELAY_MS, 0.0), SSE_RECONNECT_BASE_DELAY_MS * 2);
+        for attempt in 1..=SSE_RECONNECT_MAX_ATTEMPTS + 50 {
+            let delay = reconnect_delay_ms(attempt, SSE_RECONNECT_BASE_DELAY_MS, random_unit());
+            assert!(delay <= SSE_RECONNECT_MAX_DELAY_MS);
         }
+        assert_eq!(reconnect_delay_ms(100, SSE_RECONNECT_BASE_DELAY_MS, 0.0), SSE_RECONNECT_MAX_DELAY_MS / 2);
     }
 
-    if data_lines.is_empty() {
-        None
-    } else {
-        Some((event_name, data_lines.join("\n")))
+    #[test]
+    fn test_channel_messages_mirror_global_events() {
+        let event = SseMessage::Event { event: "chat:message-chunk".into(), data: "Hi".into() };
+        assert_eq!(event.global_event_name("t1"), "sse:t1:chat:message-chunk");
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({ "type": "event", "event": "chat:message-chunk", "data": "Hi" })
+        );
+
+        let batch = SseMessage::Batch {
+            entries: vec![SseBatchEntry { event: "chat:thinking-chunk".into(), data: "{}".into() }],
+        };
+        assert_eq!(batch.global_event_name("t1"), "sse:t1:batch");
+        assert_eq!(
+            serde_json::to_value(&batch).unwrap(),
+            serde_json::json!({ "type": "batch", "entries": [{ "event": "chat:thinking-chunk", "data": "{}" }] })
+        );
+
+        let reconnecting = SseMessage::Reconnecting(SseReconnectingEvent {
+            attempt: 1,
+            max_attempts: SSE_RECONNECT_MAX_ATTEMPTS,
+            delay_ms: 250,
+            error: "stream closed by server".into(),
+            last_event_id: Some("abc-7".into()),
+        });
+        assert_eq!(reconnecting.global_event_name("t1"), "sse:t1:reconnecting");
+        let value = serde_json::to_value(&reconnecting).unwrap();
+        assert_eq!(value["type"], "reconnecting");
+        assert_eq!(value["lastEventId"], "abc-7");
+
+        let error = SseMessage::Error { message: "gave up".into() };
+        assert_eq!(error.global_event_name("t1"), "sse:t1:error");
+        assert_eq!(serde_json::to_value(&error).unwrap()["message"], "gave up");
+
+        let stalled = SseMessage::Stalled(SseStalledEvent { silent_ms: 30_000, last_heartbeat_ms: None, heartbeats: 0 });
+        assert_eq!(stalled.global_event_name("t1"), "sse:t1:stalled");
+        assert_eq!(
+            serde_json::to_value(&stalled).unwrap(),
+            serde_json::json!({ "type": "stalled", "silentMs": 30_000, "lastHeartbeatMs": null, "heartbeats": 0 })
+        );
     }
-}
 
-/// Generic HTTP request proxy - bypasses WebView CORS entirely
-#[derive(serde::Deserialize)]
-pub struct HttpRequest {
-    pub url: String,
-    pub method: String,
-    pub body: Option<String>,
-    pub headers: Option<std::collections::HashMap<String, String>>,
-}
+    #[test]
+    fn test_stall_is_reported_once_per_silence_during_a_reply() {
+        let stall_after = Some(Duration::from_secs(30));
+        let t0 = Instant::now();
+        let at = |secs: u64| t0 + Duration::from_secs(secs);
+        let mut activity = SseActivity::new(t0);
 
-#[derive(serde::Serialize)]
-pub struct HttpResponse {
-    pub status: u16,
-    pub body: String,
-    pub headers: std::collections::HashMap<String, String>,
-    /// True if body is base64 encoded (for binary responses)
-    pub is_base64: bool,
-}
+        // Idle between replies: silence is not a stall
+        activity.event("chat:status", at(1));
+        assert_eq!(activity.stall_deadline(stall_after), None);
+        assert!(activity.check_stall(at(100), stall_after).is_none());
 
-/// Check if content type indicates binary data
-fn is_binary_content_type(content_type: &str) -> bool {
-    let ct = content_type.to_lowercase();
-    ct.starts_with("image/") ||
-    ct.starts_with("audio/") ||
-    ct.starts_with("video/") ||
-    ct.starts_with("application/octet-stream") ||
-    ct.starts_with("application/pdf")
-}
+        // Heartbeats keep coming but don't count as agent output
+        activity.event("chat:message-chunk", at(100));
+        assert_eq!(activity.stall_deadline(stall_after), Some(at(130)));
+        activity.heartbeat(at(115));
+        assert!(activity.check_stall(at(129), stall_after).is_none());
+        let stalled = activity.check_stall(at(130), stall_after).unwrap();
+        assert_eq!((stalled.silent_ms, stalled.last_heartbeat_ms, stalled.heartbeats), (30_000, Some(15_000), 1));
+        assert!(activity.check_stall(at(200), stall_after).is_none());
 
-/// Proxy an HTTP request through Rust - completely bypasses WebView CORS
-#[tauri::command]
-pub async fn proxy_http_request(app: AppHandle, request: HttpRequest) -> Result<HttpResponse, String> {
-    use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
-    use crate::logger;
-    
-    logger::info(&app, format!("[proxy] {} {} - Starting", request.method, request.url));
-    
-    // Build client with configurable timeout
-    // Enable tcp_nodelay to disable Nagle's algorithm for faster response times
-    // Force HTTP/1.1 for compatibility with Bun server (HTTP/2 may cause connection issues on Windows)
-    // Use short-lived connection pool to balance performance and stability
-    // CRITICAL: Disable proxy for localhost - reqwest uses system proxy by default!
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(HTTP_PROXY_TIMEOUT_SECS))
-        .tcp_nodelay(true)
-        .http1_only()  // Force HTTP/1.1 for stability (TODO v0.1.8: test HTTP/2 negotiation)
-        .pool_idle_timeout(std::time::Duration::from_secs(5))  // Recycle idle connections after 5s
-        .pool_max_idle_per_host(2)  // Keep up to 2 connections for reuse
-        .no_proxy()  // Disable proxy for all requests (especially localhost)
-        .build()
-        .map_err(|e| {
-            let err = format!("[proxy] Failed to create client: {}", e);
-            logger::error(&app, &err);
-            err
-        })?;
-    
-    let mut req_builder = match request.method.to_uppercase().as_str() {
-        "GET" => client.get(&request.url),
-        "POST" => client.post(&request.url),
-        "PUT" => client.put(&request.url),
-        "DELETE" => client.delete(&request.url),
-        _ => {
-            let err = format!("[proxy] Unsupported method: {}", request.method);
-            logger::error(&app, &err);
-            return Err(err);
-        }
-    };
+        // Output resumes, stalls again, then the reply ends
+        activity.event("chat:tool-use-start", at(210));
+        assert!(activity.check_stall(at(240), stall_after).is_some());
+        activity.event("chat:message-complete", at(250));
+        assert!(activity.check_stall(at(400), stall_after).is_none());
+
+        // sseStallTimeoutSecs = 0 disables detection
+        activity.event("chat:thinking-chunk", at(400));
+        assert!(activity.check_stall(at(1000), None).is_none());
+    }
 
-    // Add headers
-    if let Some(headers) = request.headers {
-        for (key, value) in headers {
-            req_builder = req_builder.header(&key, &value);
+    #[test]
+    fn test_replay_buffer_is_bounded_and_skips_status() {
+        let event = |data: String| SseMessage::Event { event: "chat:message-chunk".into(), data };
+        let mut replay = SseReplayBuffer::default();
+        replay.push(&SseMessage::Error { message: "gave up".into() });
+        replay.push(&SseMessage::Reconnected(SseReconnectedEvent { attempts: 1, last_event_id: None }));
+        assert_eq!(replay.iter().count(), 0);
+
+        for i in 0..SSE_REPLAY_MAX_MESSAGES + 10 {
+            replay.push(&event(i.to_string()));
         }
+        let kept: Vec<String> = replay.iter().map(|m| match m {
+            SseMessage::Event { data, .. } => data.clone(),
+            _ => unreachable!(),
+        }).collect();
+        assert_eq!(kept.len(), SSE_REPLAY_MAX_MESSAGES);
+        assert_eq!(kept[0], "10");
+
+        // Byte bound evicts the oldest, but the newest message is always kept
+        replay.push(&event("x".repeat(SSE_REPLAY_MAX_BYTES)));
+        assert_eq!(replay.iter().count(), 1);
+        assert!(replay.bytes > SSE_REPLAY_MAX_BYTES);
+        replay.push(&SseMessage::Batch {
+            entries: vec![SseBatchEntry { event: "chat:thinking-chunk".into(), data: "{}".into() }],
+        });
+        assert_eq!(replay.iter().count(), 1);
+        assert!(matches!(replay.iter().next(), Some(SseMessage::Batch { .. })));
     }
 
-    // Add body for POST/PUT
-    if let Some(ref body) = request.body {
-        logger::debug(&app, format!("[proxy] Request body length: {} bytes", body.len()));
-        req_builder = req_builder.header("Content-Type", "application/json");
-        req_builder = req_builder.body(body.clone());
+    #[test]
+    fn test_replay_buffer_is_cleared_when_the_stream_moves() {
+        let event = SseMessage::Event { event: "chat:message-chunk".into(), data: "old".into() };
+        let mut replay = SseReplayBuffer::default();
+        assert!(!replay.retarget(&sidecar_stream_url(31415)));
+        replay.push(&event);
+
+        // Reattaching to the same sidecar (task restarted) keeps the history
+        assert!(!replay.retarget(&sidecar_stream_url(31415)));
+        assert_eq!(replay.iter().count(), 1);
+
+        // A restarted sidecar on another port starts a new session
+        assert!(replay.retarget(&sidecar_stream_url(31416)));
+        assert_eq!(replay.iter().count(), 0);
+        assert_eq!(replay.bytes, 0);
+        replay.push(&event);
+        assert_eq!(replay.iter().count(), 1);
     }
 
-    logger::info(&app, format!("[proxy] {} {} - Sending request...", request.method, request.url));
-    
-    // Send request with detailed error logging
-    let response = req_builder.send().await.map_err(|e| {
-        let mut err = format!("[proxy] Request failed: {}", e);
+    #[test]
+    fn test_batcher_merges_text_and_keeps_order() {
+        let mut batcher = SseBatcher::new(16);
+        assert!(batcher.accepts("chat:thinking-chunk"));
+        assert!(!batcher.accepts("chat:message-complete"));
+        assert!(batcher.deadline().is_none());
 
-        // Add detailed error information for debugging
-        if e.is_connect() {
-            err.push_str(" (Connection error - cannot establish connection)");
-        }
-        if e.is_timeout() {
-            err.push_str(" (Timeout error - request took too long)");
-        }
-        if e.is_request() {
-            err.push_str(" (Request error - invalid request)");
-        }
-        if e.is_body() {
-            err.push_str(" (Body error - failed to read response body)");
-        }
+        assert!(!batcher.push("chat:message-chunk".into(), "Hel".into()));
+        assert!(batcher.deadline().is_some());
+        assert!(!batcher.push("chat:message-chunk".into(), "lo\n".into()));
+        assert!(!batcher.push("chat:thinking-chunk".into(), r#"{"index":0,"delta":"a"}"#.into()));
+        assert!(!batcher.push("chat:thinking-chunk".into(), r#"{"index":0,"delta":"b"}"#.into()));
+        assert!(!batcher.push("chat:message-chunk".into(), "世界".into()));
 
-        // Try to get the source error
-        if let Some(source) = e.source() {
-            err.push_str(&format!(" | Source: {}", source));
-        }
+        let entries = batcher.take();
+        let events: Vec<(&str, &str)> = entries.iter().map(|e| (e.event.as_str(), e.data.as_str())).collect();
+        assert_eq!(events, vec![
+            ("chat:message-chunk", "Hello\n"),
+            ("chat:thinking-chunk", r#"{"index":0,"delta":"a"}"#),
+            ("chat:thinking-chunk", r#"{"index":0,"delta":"b"}"#),
+            ("chat:message-chunk", "世界"),
+        ]);
+        assert!(batcher.deadline().is_none());
+        assert!(batcher.take().is_empty());
+    }
 
-        logger::error(&app, &err);
-        e.to_string()
-    })?;
-    
-    let status = response.status().as_u16();
-    
-    // Collect response headers
-    let mut resp_headers = std::collections::HashMap::new();
-    for (key, value) in response.headers() {
-        if let Ok(v) = value.to_str() {
-            resp_headers.insert(key.to_string(), v.to_string());
-        }
+    #[test]
+    fn test_batcher_flushes_when_full_or_disabled() {
+        let mut batcher = SseBatcher::new(16);
+        let full = (0..SSE_BATCH_MAX_ENTRIES)
+            .map(|i| batcher.push("chat:tool-input-delta".into(), i.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(full.iter().filter(|&&f| f).count(), 1);
+        assert!(full[SSE_BATCH_MAX_ENTRIES - 1]);
+
+        assert_eq!(batcher.take().len(), SSE_BATCH_MAX_ENTRIES);
+        assert!(batcher.push("chat:message-chunk".into(), "x".repeat(SSE_BATCH_MAX_BYTES)));
+
+        assert!(!SseBatcher::new(0).accepts("chat:message-chunk"));
     }
-    
-    // Check if this is binary content
-    let content_type = resp_headers.get("content-type")
-        .map(|s| s.as_str())
-        .unwrap_or("");
-    
-    let is_binary = is_binary_content_type(content_type);
-    
-    // Get response body - encode as base64 if binary
-    let (body, is_base64) = if is_binary {
-        let bytes = response.bytes().await.map_err(|e| {
-            let err = format!("[proxy] Failed to read binary body: {}", e);
-            logger::error(&app, &err);
-            e.to_string()
-        })?;
-        logger::debug(&app, format!("[proxy] Binary response: {} bytes", bytes.len()));
-        (BASE64.encode(&bytes), true)
-    } else {
-        let text = response.text().await.map_err(|e| {
-            let err = format!("[proxy] Failed to read text body: {}", e);
-            logger::error(&app, &err);
-            e.to_string()
-        })?;
-        logger::debug(&app, format!("[proxy] Text response: {} bytes", text.len()));
-        (text, false)
-    };
-    
-    logger::info(&app, format!("[proxy] {} {} - Complete (status: {}, body: {} bytes)", 
-        request.method, request.url, status, body.len()));
-    
-    Ok(HttpResponse {
-        status,
-        body,
-        headers: resp_headers,
-        is_base64,
-    })
 }
@@ -0,0 +1,517 @@
+// SSE recorder - captures a Tab's event stream to disk and replays it for debugging
+//
+// Enabled with runtimeSettings.sseRecordingEnabled (takes effect on the next
+// start_sse_proxy). Each Tab connection writes ~/.myagents/recordings/{tab}-{timestamp}.sse.
+//
+// The file holds the raw network chunks exactly as they arrived, before SseDecoder saw
+// them, so decoder bugs (split UTF-8, CRLF across chunks...) reproduce on replay. Every
+// frame is a text header `{kind} {ms} {len}\n` followed by `len` payload bytes and `\n`:
+// - `chunk`: bytes received from the sidecar
+// - `note`: connection notes (header, connect, disconnect), UTF-8 text
+// `ms` is the time since recording started (monotonic clock).
+//
+// replay_sse_recording feeds the chunks back through a fresh SseDecoder per connection
+// and publishes every event, at original speed or accelerated, through the Tab's replay
+// hub: subscribers join it with start_sse_proxy `replay` and receive the events exactly
+// like live ones. The sidecar is never involved (it need not be running), and live
+// subscribers of the Tab don't see replayed events.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use tauri::AppHandle;
+
+use crate::logger;
+use crate::sse_decoder::{SseDecoder, SseEvent, SseItem};
+use crate::sse_proxy::{self, SseMessage, SseProxyState};
+
+/// Frame carrying bytes received from the sidecar
+const CHUNK_FRAME: &str = "chunk";
+/// Frame carrying a connection note
+const NOTE_FRAME: &str = "note";
+/// Note written when a (re)connection succeeds; the live decoder restarts there
+const CONNECTED_NOTE_PREFIX: &str = "connected:";
+/// Payload of the first note of every recording
+const RECORDING_HEADER: &str = "myagents-sse-recording v2";
+/// Longest delay between receiving a frame and flushing it to disk
+const RECORDING_FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+/// Longest single pause honoured during replay (long idle gaps are compressed)
+const REPLAY_MAX_GAP_MS: u64 = 5_000;
+
+/// Get recordings directory path (~/.myagents/recordings/)
+fn recordings_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".myagents")
+        .join("recordings")
+}
+
+/// Tab IDs end up in file names; keep only safe characters
+fn sanitize_tab_id(tab_id: &str) -> String {
+    let sanitized: String = tab_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if sanitized.is_empty() { "tab".to_string() } else { sanitized }
+}
+
+/// Serialize one frame
+fn encode_frame(kind: &str, elapsed_ms: u64, payload: &[u8]) -> Vec<u8> {
+    let mut frame = format!("{} {} {}\n", kind, elapsed_ms, payload.len()).into_bytes();
+    frame.extend_from_slice(payload);
+    frame.push(b'\n');
+    frame
+}
+
+/// Writes one Tab's stream to a recording file
+///
+/// Frames are handed to a blocking writer task over a channel, so the SSE task never
+/// waits on disk I/O. The writer flushes every RECORDING_FLUSH_INTERVAL and when the
+/// recorder is dropped (the connection task ended).
+pub struct SseRecorder {
+    frames: mpsc::Sender<Vec<u8>>,
+    started: Instant,
+    path: PathBuf,
+}
+
+impl SseRecorder {
+    /// Create `~/.myagents/recordings/{tab}-{timestamp}.sse`
+    pub fn create(tab_id: &str, url: &str) -> Result<Self, String> {
+        let dir = recordings_dir();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("[sse-recorder] Failed to create {}: {}", dir.display(), e))?;
+
+        let started_at = chrono::Local::now();
+        let path = dir.join(format!(
+            "{}-{}.sse",
+            sanitize_tab_id(tab_id),
+            started_at.format("%Y%m%d-%H%M%S%.3f")
+        ));
+        let file = File::create(&path)
+            .map_err(|e| format!("[sse-recorder] Failed to create {}: {}", path.display(), e))?;
+
+        let (frames, receiver) = mpsc::channel();
+        let writer_tab_id = tab_id.to_string();
+        let writer_path = path.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            if let Err(e) = write_frames(BufWriter::new(file), &receiver, RECORDING_FLUSH_INTERVAL) {
+                log::warn!(
+                    "[sse-recorder] Tab {} recording stopped, write to {} failed: {}",
+                    writer_tab_id, writer_path.display(), e
+                );
+            }
+        });
+
+        let recorder = Self { frames, started: Instant::now(), path };
+        recorder.note(&format!(
+            "{} tab={} url={} started={}",
+            RECORDING_HEADER, tab_id, url, started_at.to_rfc3339()
+        ));
+        Ok(recorder)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn write_frame(&self, kind: &str, payload: &[u8]) {
+        // Fails only after the writer gave up (already logged)
+        let _ = self.frames.send(encode_frame(kind, self.started.elapsed().as_millis() as u64, payload));
+    }
+
+    /// Record a network chunk as received (before decoding)
+    pub fn record_chunk(&self, chunk: &[u8]) {
+        self.write_frame(CHUNK_FRAME, chunk);
+    }
+
+    /// Record a connection note (connect, error, reconnect)
+    pub fn note(&self, note: &str) {
+        self.write_frame(NOTE_FRAME, note.as_bytes());
+    }
+
+    /// Note a successful (re)connection; replay restarts decoding there
+    pub fn note_connected(&self, detail: &str) {
+        self.note(&format!("{} {}", CONNECTED_NOTE_PREFIX, detail));
+    }
+}
+
+/// Writer loop: append frames until every sender is gone, flushing at most `interval`
+/// after a write (a crash or freeze loses at most that much of the recording)
+fn write_frames(
+    mut writer: impl Write,
+    frames: &mpsc::Receiver<Vec<u8>>,
+    interval: Duration,
+) -> std::io::Result<()> {
+    let mut flush_at: Option<Instant> = None;
+    loop {
+        let received = match flush_at {
+            Some(deadline) => frames.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => frames.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(frame) => {
+                writer.write_all(&frame)?;
+                flush_at.get_or_insert_with(|| Instant::now() + interval);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return writer.flush(),
+        }
+        if flush_at.is_some_and(|deadline| deadline <= Instant::now()) {
+            writer.flush()?;
+            flush_at = None;
+        }
+    }
+}
+
+/// A frame read back from a recording
+#[derive(Debug, Clone, PartialEq)]
+struct Frame<'a> {
+    kind: &'a str,
+    at_ms: u64,
+    payload: &'a [u8],
+}
+
+/// Split a recording into frames; a frame cut off by a crash keeps its available bytes,
+/// anything unparseable ends the recording
+fn parse_frames(mut bytes: &[u8]) -> Vec<Frame<'_>> {
+    let mut frames = Vec::new();
+    while let Some(header_end) = bytes.iter().position(|&b| b == b'\n') {
+        let Ok(header) = std::str::from_utf8(&bytes[..header_end]) else {
+            break;
+        };
+        let mut fields = header.split(' ');
+        let (Some(kind), Some(Ok(at_ms)), Some(Ok(len)), None) = (
+            fields.next(),
+            fields.next().map(str::parse::<u64>),
+            fields.next().map(str::parse::<usize>),
+            fields.next(),
+        ) else {
+            break;
+        };
+        let body = &bytes[header_end + 1..];
+        let payload = &body[..len.min(body.len())];
+        frames.push(Frame { kind, at_ms, payload });
+        // Payload plus its trailing newline
+        bytes = body.get(len + 1..).unwrap_or_default();
+    }
+    frames
+}
+
+/// An event decoded from a recording
+#[derive(Debug, Clone, PartialEq)]
+struct RecordedEvent {
+    /// Milliseconds since the recording started (arrival of the completing chunk)
+    at_ms: u64,
+    event: SseEvent,
+}
+
+/// Decode a recording's chunks like the live proxy did: one decoder per connection,
+/// so an event cut off by a disconnect is dropped here too
+fn parse_recording(bytes: &[u8]) -> Vec<RecordedEvent> {
+    let mut decoder = SseDecoder::new();
+    let mut events = Vec::new();
+    for frame in parse_frames(bytes) {
+        match frame.kind {
+            CHUNK_FRAME => {
+                for item in decoder.feed(frame.payload) {
+                    if let SseItem::Event(event) = item {
+                        events.push(RecordedEvent { at_ms: frame.at_ms, event });
+                    }
+                }
+            }
+            NOTE_FRAME if frame.payload.starts_with(CONNECTED_NOTE_PREFIX.as_bytes()) => {
+                decoder = SseDecoder::new();
+            }
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Delay before replaying an event `gap_ms` after the previous one
+fn replay_delay(gap_ms: u64, speed: f64) -> Duration {
+    if speed <= 0.0 {
+        return Duration::ZERO;
+    }
+    Duration::from_secs_f64(gap_ms.min(REPLAY_MAX_GAP_MS) as f64 / 1000.0 / speed)
+}
+
+/// Resolve a recording path relative to the recordings directory
+fn resolve_recording_path(path: &str) -> Result<PathBuf, String> {
+    resolve_recording_path_in(&recordings_dir(), path)
+}
+
+/// Resolve `path` inside `dir`: absolute paths and `..` are rejected, and the canonical
+/// result (symlinks followed) must still be inside `dir`
+fn resolve_recording_path_in(dir: &Path, path: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(path);
+    let plain = relative
+        .components()
+        .all(|component| matches!(component, std::path::Component::Normal(_)));
+    if path.is_empty() || !plain {
+        return Err(format!(
+            "[sse-recorder] Invalid recording path {:?}: expected a path inside {}",
+            path, dir.display()
+        ));
+    }
+
+    let dir = dir
+        .canonicalize()
+        .map_err(|e| format!("[sse-recorder] Failed to open {}: {}", dir.display(), e))?;
+    let resolved = dir
+        .join(relative)
+        .canonicalize()
+        .map_err(|e| format!("[sse-recorder] Failed to open recording {:?}: {}", path, e))?;
+    if !resolved.starts_with(&dir) {
+        return Err(format!("[sse-recorder] Recording {:?} is outside {}", path, dir.display()));
+    }
+    Ok(resolved)
+}
+
+/// Result of replay_sse_recording
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SseReplaySummary {
+    pub path: String,
+    pub events: usize,
+    /// Wall-clock time the replay took
+    pub duration_ms: u64,
+}
+
+/// Publish a recording into a Tab's stream, as if its sidecar sent the events again
+///
+/// `path` is relative to ~/.myagents/recordings/ (usually just the file name).
+/// `speed`: 1.0 (default) replays at the original pace, 10.0 ten times faster,
+/// 0 emits everything back-to-back. Gaps longer than 5s are shortened to 5s.
+/// Events go to the Tab's replay hub (created if needed); late subscribers get the
+/// most recent ones from its replay buffer.
+#[tauri::command]
+pub async fn replay_sse_recording(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<SseProxyState>>,
+    path: String,
+    tab_id: String,
+    speed: Option<f64>,
+) -> Result<SseReplaySummary, String> {
+    let path = resolve_recording_path(&path)?;
+    let speed = speed.unwrap_or(1.0);
+    if !speed.is_finite() || speed < 0.0 {
+        return Err(format!("[sse-recorder] Invalid replay speed: {}", speed));
+    }
+
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| format!("[sse-recorder] Failed to read {}: {}", path.display(), e))?;
+    let events = parse_recording(&bytes);
+    let hub = sse_proxy::replay_hub(&app, &state, &tab_id).await;
+
+    logger::info(&app, format!(
+        "[sse-recorder] Replaying {} events from {} into tab {} at {}x",
+        events.len(), path.display(), tab_id, speed
+    ));
+
+    let started = Instant::now();
+    publish_events(&events, speed, |message| hub.broadcast(message)).await;
+
+    let summary = SseReplaySummary {
+        path: path.display().to_string(),
+        events: events.len(),
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    logger::info(&app, format!(
+        "[sse-recorder] Replay into tab {} finished: {} events in {}ms",
+        tab_id, summary.events, summary.duration_ms
+    ));
+    Ok(summary)
+}
+
+/// Hand `events` to `publish` as event messages, paced by their recorded times
+async fn publish_events(events: &[RecordedEvent], speed: f64, mut publish: impl FnMut(SseMessage)) {
+    let mut previous_ms = events.first().map(|e| e.at_ms).unwrap_or(0);
+    for recorded in events {
+        let delay = replay_delay(recorded.at_ms.saturating_sub(previous_ms), speed);
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        previous_ms = recorded.at_ms;
+        publish(SseMessage::Event {
+            event: recorded.event.event.clone(),
+            data: recorded.event.data.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(name: &str, data: &str, id: Option<&str>) -> SseEvent {
+        SseEvent { event: name.to_string(), data: data.to_string(), id: id.map(str::to_string) }
+    }
+
+    fn recording(frames: &[(&str, u64, &[u8])]) -> Vec<u8> {
+        frames.iter().flat_map(|(kind, ms, payload)| encode_frame(kind, *ms, payload)).collect()
+    }
+
+    #[test]
+    fn test_raw_chunks_are_decoded_on_replay() {
+        let message = "data: 第一行\r\n".as_bytes();
+        let file = recording(&[
+            (NOTE_FRAME, 0, format!("{} tab=t1", RECORDING_HEADER).as_bytes()),
+            (NOTE_FRAME, 1, b"connected: 200 OK"),
+            // Split inside a multi-byte character and between CR and LF
+            (CHUNK_FRAME, 10, b"id: abc-1\r\nevent: chat:message-chunk\r\n"),
+            (CHUNK_FRAME, 12, &message[..8]),
+            (CHUNK_FRAME, 40, &message[8..message.len() - 1]),
+            (CHUNK_FRAME, 41, b"\n\r\n: ping\n\ndata: {}\n\n"),
+        ]);
+
+        let recorded = parse_recording(&file);
+        assert_eq!(
+            recorded,
+            vec![
+                RecordedEvent { at_ms: 41, event: event("chat:message-chunk", "第一行", Some("abc-1")) },
+                RecordedEvent { at_ms: 41, event: event("message", "{}", Some("abc-1")) },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reconnect_drops_the_cut_off_event() {
+        let file = recording(&[
+            (NOTE_FRAME, 0, b"connected: 200 OK"),
+            (CHUNK_FRAME, 5, b"data: a\n\ndata: cut"),
+            (NOTE_FRAME, 6, b"disconnected: stream closed by server"),
+            (NOTE_FRAME, 900, b"connected: 200 OK"),
+            (CHUNK_FRAME, 910, b"data: b\n\n"),
+        ]);
+        let recorded = parse_recording(&file);
+        let data: Vec<(u64, &str)> = recorded.iter().map(|r| (r.at_ms, r.event.data.as_str())).collect();
+        assert_eq!(data, vec![(5, "a"), (910, "b")]);
+    }
+
+    #[test]
+    fn test_truncated_recording_keeps_complete_frames() {
+        let mut file = recording(&[(CHUNK_FRAME, 5, b"data: a\n\n")]);
+        // App killed mid-frame: the available bytes still count
+        file.extend_from_slice(b"chunk 9 20\ndata: b\n\nda");
+        let recorded = parse_recording(&file);
+        let data: Vec<(u64, &str)> = recorded.iter().map(|r| (r.at_ms, r.event.data.as_str())).collect();
+        assert_eq!(data, vec![(5, "a"), (9, "b")]);
+
+        // Garbage ends the recording instead of misreading payloads as headers
+        file = recording(&[(CHUNK_FRAME, 5, b"data: a\n\n")]);
+        file.extend_from_slice(b"not a frame\ndata: b\n\n");
+        assert_eq!(parse_recording(&file).len(), 1);
+    }
+
+    /// Counts flushes so the timer can be observed
+    #[derive(Default)]
+    struct FlushCounter {
+        written: Vec<u8>,
+        flushes: usize,
+    }
+
+    impl Write for &mut FlushCounter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_writer_flushes_on_a_timer_and_on_close() {
+        let (frames, receiver) = mpsc::channel();
+        for n in 0..100u8 {
+            frames.send(vec![n]).unwrap();
+        }
+        let sender = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(80));
+            frames.send(vec![100]).unwrap();
+        });
+
+        let mut output = FlushCounter::default();
+        write_frames(&mut output, &receiver, Duration::from_millis(20)).unwrap();
+        sender.join().unwrap();
+
+        assert_eq!(output.written, (0..=100u8).collect::<Vec<_>>());
+        // One timed flush after the burst (not one per frame), then the final one on close
+        assert_eq!(output.flushes, 2);
+    }
+
+    #[test]
+    fn test_replay_into_a_tab_without_sidecar() {
+        let file = recording(&[
+            (NOTE_FRAME, 0, format!("{} tab=idle", RECORDING_HEADER).as_bytes()),
+            (NOTE_FRAME, 1, b"connected: 200 OK"),
+            (CHUNK_FRAME, 10, b"event: chat:message-chunk\ndata: Hi\n\n"),
+            (CHUNK_FRAME, 30, b"event: chat:message-complete\ndata: {}\n\n"),
+        ]);
+        let events = parse_recording(&file);
+
+        // Publishing only needs the recording and a hub, no upstream connection
+        let mut published = Vec::new();
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(publish_events(&events, 0.0, |message| published.push(message)));
+
+        let published: Vec<(String, String)> = published
+            .into_iter()
+            .map(|message| match message {
+                SseMessage::Event { event, data } => (event, data),
+                other => panic!("unexpected message {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            published,
+            vec![
+                ("chat:message-chunk".to_string(), "Hi".to_string()),
+                ("chat:message-complete".to_string(), "{}".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replay_delay_and_names() {
+        assert_eq!(replay_delay(100, 1.0), Duration::from_millis(100));
+        assert_eq!(replay_delay(100, 4.0), Duration::from_millis(25));
+        assert_eq!(replay_delay(100, 0.0), Duration::ZERO);
+        assert_eq!(replay_delay(60_000, 1.0), Duration::from_millis(REPLAY_MAX_GAP_MS));
+
+        assert_eq!(sanitize_tab_id("tab/../x y"), "tab____x_y");
+    }
+
+    #[test]
+    fn test_recording_paths_stay_inside_the_recordings_dir() {
+        let root = std::env::temp_dir().join(format!("myagents-rec-test-{}", uuid::Uuid::new_v4()));
+        let dir = root.join("recordings");
+        std::fs::create_dir_all(dir.join("old")).unwrap();
+        std::fs::write(dir.join("t1-1.sse"), b"").unwrap();
+        std::fs::write(dir.join("old").join("t1-0.sse"), b"").unwrap();
+        std::fs::write(root.join("secret.txt"), b"").unwrap();
+        let canonical = dir.canonicalize().unwrap();
+
+        assert_eq!(resolve_recording_path_in(&dir, "t1-1.sse"), Ok(canonical.join("t1-1.sse")));
+        assert_eq!(resolve_recording_path_in(&dir, "old/t1-0.sse"), Ok(canonical.join("old").join("t1-0.sse")));
+        for rejected in ["", "../secret.txt", "old/../t1-1.sse", "./t1-1.sse", "missing.sse"] {
+            assert!(resolve_recording_path_in(&dir, rejected).is_err(), "{:?}", rejected);
+        }
+        let absolute = dir.join("t1-1.sse");
+        assert!(resolve_recording_path_in(&dir, absolute.to_str().unwrap()).is_err());
+
+        // A symlink pointing out of the directory is caught after canonicalization
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("secret.txt"), dir.join("link.sse")).unwrap();
+            assert!(resolve_recording_path_in(&dir, "link.sse").is_err());
+        }
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
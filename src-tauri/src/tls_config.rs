@@ -0,0 +1,333 @@
+//! TLS trust settings for outbound connections
+//!
+//! `tlsSettings` in `~/.myagents/config.json` decides which certificate authorities
+//! outbound HTTPS trusts, which matters behind TLS-inspecting corporate proxies:
+//! - `caBundles`: extra PEM bundle files (`~/` is expanded)
+//! - `useSystemStore`: trust the OS certificate store (default: true); when false only
+//!   `caBundles` are trusted by the Rust clients (a configuration error if none loads)
+//! - `insecure`: skip certificate verification entirely (debugging only, logged loudly)
+//!
+//! Applied to reqwest clients (`proxy_config::build_client_with_proxy`), the updater,
+//! the proxy connectivity test and the Bun sidecar, which receives the same CAs via
+//! `NODE_EXTRA_CA_CERTS` so the Claude Agent SDK trusts what the app trusts.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use base64::Engine;
+use serde::Deserialize;
+use tokio_rustls::rustls;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::CertificateDer;
+
+use crate::app_config;
+
+/// Merged CA bundle handed to sidecars (relative to `~/.myagents`)
+const SIDECAR_CA_BUNDLE_PATH: &str = "tls/extra-ca.pem";
+
+/// TLS environment variables understood by Bun/Node tooling
+/// Sidecars remove all of them before applying the configured ones.
+pub const TLS_ENV_VARS: [&str; 3] = [
+    "NODE_EXTRA_CA_CERTS",
+    "NODE_USE_SYSTEM_CA",
+    "NODE_TLS_REJECT_UNAUTHORIZED",
+];
+
+/// TLS settings from `~/.myagents/config.json`
+///
+/// # Example JSON
+/// ```json
+/// {
+///   "tlsSettings": {
+///     "caBundles": ["~/certs/corp-root.pem"],
+///     "useSystemStore": true,
+///     "insecure": false
+///   }
+/// }
+/// ```
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TlsSettings {
+    /// Extra PEM bundles to trust
+    pub ca_bundles: Vec<String>,
+    /// Trust the OS certificate store (default: true)
+    pub use_system_store: bool,
+    /// Disable certificate verification (dangerous)
+    pub insecure: bool,
+}
+
+impl Default for TlsSettings {
+    fn default() -> Self {
+        Self {
+            ca_bundles: Vec::new(),
+            use_system_store: true,
+            insecure: false,
+        }
+    }
+}
+
+/// TLS settings from the cached config (defaults when the section is missing)
+pub fn read_tls_settings() -> TlsSettings {
+    app_config::current().tls_settings.clone()
+}
+
+/// Expand a leading `~/` to the home directory
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Parse every certificate in a PEM bundle
+fn parse_pem_bundle(pem: &[u8]) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certs = CertificateDer::pem_slice_iter(pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("invalid PEM: {}", e))?;
+    if certs.is_empty() {
+        return Err("no certificates found".to_string());
+    }
+    Ok(certs)
+}
+
+/// Certificates from `caBundles`; unreadable or invalid bundles are logged and skipped
+fn load_extra_cas(settings: &TlsSettings) -> Vec<CertificateDer<'static>> {
+    let mut certs = Vec::new();
+    for bundle in &settings.ca_bundles {
+        let path = expand_home(bundle);
+        match fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|pem| parse_pem_bundle(&pem))
+        {
+            Ok(bundle_certs) => certs.extend(bundle_certs),
+            Err(e) => log::error!("[tls_config] Ignoring CA bundle {:?}: {}", path, e),
+        }
+    }
+    certs
+}
+
+/// Whether the OS store is trusted; without it and without extra CAs nothing would
+/// verify, which is reported as a configuration error rather than guessed around
+fn trusts_system_store(settings: &TlsSettings, extra_cas: usize) -> Result<bool, String> {
+    if !settings.use_system_store && extra_cas == 0 {
+        return Err(
+            "tlsSettings.useSystemStore is false but no certificate could be loaded from caBundles, \
+             so no server would be trusted. Fix caBundles or enable useSystemStore."
+                .to_string(),
+        );
+    }
+    Ok(settings.use_system_store)
+}
+
+/// Loud warning whenever verification is disabled
+fn warn_insecure(target: &str) {
+    log::warn!(
+        "[tls_config] ⚠️ TLS CERTIFICATE VERIFICATION IS DISABLED for {} (tlsSettings.insecure). \
+         Any network hop can read and modify this traffic; only use this for debugging.",
+        target
+    );
+}
+
+/// Apply TLS settings to a reqwest client builder
+pub fn apply_tls_settings(builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder, String> {
+    let settings = read_tls_settings();
+    if settings.insecure {
+        warn_insecure("HTTP clients");
+        return Ok(builder.danger_accept_invalid_certs(true));
+    }
+
+    let extra_cas = load_extra_cas(&settings);
+    let use_system_store = trusts_system_store(&settings, extra_cas.len())?;
+    if extra_cas.is_empty() {
+        return Ok(builder);
+    }
+    let certs = extra_cas
+        .iter()
+        .map(|der| reqwest::Certificate::from_der(der))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("[tls_config] Invalid CA certificate: {}", e))?;
+    log::info!(
+        "[tls_config] Trusting {} extra CA certificate(s){}",
+        certs.len(),
+        if use_system_store { "" } else { " only (OS store disabled)" }
+    );
+    Ok(if use_system_store {
+        builder.tls_certs_merge(certs)
+    } else {
+        builder.tls_certs_only(certs)
+    })
+}
+
+/// Client hook for the updater plugin (reqwest 0.12 with bundled webpki roots)
+/// The OS store is added explicitly, which is what TLS-inspecting proxies need.
+pub fn updater_client_config(
+) -> Result<impl Fn(updater_reqwest::ClientBuilder) -> updater_reqwest::ClientBuilder + Send + Sync + 'static, String> {
+    let settings = read_tls_settings();
+    let mut der_certs = load_extra_cas(&settings);
+    let use_system_store = settings.insecure || trusts_system_store(&settings, der_certs.len())?;
+    if use_system_store && !settings.insecure {
+        let native = rustls_native_certs::load_native_certs();
+        for e in &native.errors {
+            log::warn!("[tls_config] Failed to load some OS certificates: {}", e);
+        }
+        der_certs.extend(native.certs);
+    }
+    let certs: Vec<updater_reqwest::Certificate> = der_certs
+        .iter()
+        .filter_map(|der| updater_reqwest::Certificate::from_der(der).ok())
+        .collect();
+    if settings.insecure {
+        warn_insecure("the updater");
+    }
+
+    Ok(move |builder: updater_reqwest::ClientBuilder| {
+        if settings.insecure {
+            return builder.danger_accept_invalid_certs(true);
+        }
+        let builder = builder.tls_built_in_root_certs(use_system_store);
+        certs
+            .iter()
+            .cloned()
+            .fold(builder, |builder, cert| builder.add_root_certificate(cert))
+    })
+}
+
+/// rustls client config honoring the CA settings (used by the proxy connectivity test)
+/// `insecure` is deliberately ignored there so interception is still reported.
+pub fn rustls_client_config() -> Result<rustls::ClientConfig, String> {
+    let settings = read_tls_settings();
+    let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+    let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?;
+
+    let extra_cas = load_extra_cas(&settings);
+    let config = if trusts_system_store(&settings, extra_cas.len())? {
+        let verifier = if extra_cas.is_empty() {
+            rustls_platform_verifier::Verifier::new(provider)
+        } else {
+            rustls_platform_verifier::Verifier::new_with_extra_roots(extra_cas, provider)
+        }
+        .map_err(|e| e.to_string())?;
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth()
+    } else {
+        let mut roots = rustls::RootCertStore::empty();
+        let (_, ignored) = roots.add_parsable_certificates(extra_cas);
+        if ignored > 0 {
+            log::warn!("[tls_config] Ignored {} unusable CA certificate(s)", ignored);
+        }
+        builder.with_root_certificates(roots).with_no_client_auth()
+    };
+    Ok(config)
+}
+
+/// PEM text for DER certificates
+fn encode_pem(certs: &[CertificateDer<'_>]) -> String {
+    let mut pem = String::new();
+    for cert in certs {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(cert);
+        pem.push_str("-----BEGIN CERTIFICATE-----\n");
+        for line in encoded.as_bytes().chunks(64) {
+            pem.push_str(&String::from_utf8_lossy(line));
+            pem.push('\n');
+        }
+        pem.push_str("-----END CERTIFICATE-----\n");
+    }
+    pem
+}
+
+/// Write the merged CA bundle for sidecars (only rewritten when it changes)
+fn write_sidecar_ca_bundle(certs: &[CertificateDer<'_>]) -> Result<PathBuf, String> {
+    let path = app_config::config_file_path()
+        .ok_or("Failed to get home directory")?
+        .with_file_name(SIDECAR_CA_BUNDLE_PATH);
+    let pem = encode_pem(certs);
+    if fs::read_to_string(&path).ok().as_deref() != Some(pem.as_str()) {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        }
+        fs::write(&path, pem).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    }
+    Ok(path)
+}
+
+/// TLS environment variables for the Bun sidecar (Claude Agent SDK → Anthropic API)
+/// Bun always keeps its bundled roots; `NODE_USE_SYSTEM_CA` adds the OS store and
+/// `NODE_EXTRA_CA_CERTS` the configured bundles.
+pub fn sidecar_tls_env() -> Result<Vec<(String, String)>, String> {
+    let settings = read_tls_settings();
+    let mut env = Vec::new();
+    if settings.insecure {
+        warn_insecure("the Claude Agent SDK sidecar");
+        env.push(("NODE_TLS_REJECT_UNAUTHORIZED".to_string(), "0".to_string()));
+        return Ok(env);
+    }
+
+    let extra_cas = load_extra_cas(&settings);
+    if trusts_system_store(&settings, extra_cas.len())? {
+        env.push(("NODE_USE_SYSTEM_CA".to_string(), "1".to_string()));
+    }
+    if !extra_cas.is_empty() {
+        match write_sidecar_ca_bundle(&extra_cas) {
+            Ok(path) => env.push(("NODE_EXTRA_CA_CERTS".to_string(), path.to_string_lossy().into_owned())),
+            Err(e) => log::error!("[tls_config] Sidecar will not trust the extra CAs: {}", e),
+        }
+    }
+    Ok(env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Self-signed test certificate (CN=myagents-test)
+    const TEST_CERT_PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBhTCCASugAwIBAgIUA6rwQGJlpG2Fn0ihfXPnLNGXe9gwCgYIKoZIzj0EAwIw
+GDEWMBQGA1UEAwwNbXlhZ2VudHMtdGVzdDAeFw0yNjEwMTgwMjE2MDRaFw0zNjEw
+MTUwMjE2MDRaMBgxFjAUBgNVBAMMDW15YWdlbnRzLXRlc3QwWTATBgcqhkjOPQIB
+BggqhkjOPQMBBwNCAASOdwUoQeEtbi0XKveJTUf4NtDlyIvQ28d7fngFfjHVDA4y
+Ck9vCC5VsiNkB5eNpd6IX5EpOnM+y6tOn76l6I4Zo1MwUTAdBgNVHQ4EFgQUphNJ
+emQJqwqR832/0c8YUqXhzjcwHwYDVR0jBBgwFoAUphNJemQJqwqR832/0c8YUqXh
+zjcwDwYDVR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNIADBFAiA0ow3645KeTF/g
+HO3GZ8crjFq5yLnllZqybRsT0NjLhgIhAJZfxgPVJFSF8Lm3sS/lyZJe1NJHCyLq
+P/qMA/sPj6ka
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn test_defaults_trust_system_store() {
+        let settings: TlsSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings, TlsSettings::default());
+        assert!(settings.use_system_store);
+        assert!(!settings.insecure);
+    }
+
+    #[test]
+    fn test_parse_pem_bundle() {
+        let certs = parse_pem_bundle(TEST_CERT_PEM.as_bytes()).unwrap();
+        assert_eq!(certs.len(), 1);
+        let bundle = format!("# corp roots\n{}{}", TEST_CERT_PEM, TEST_CERT_PEM);
+        assert_eq!(parse_pem_bundle(bundle.as_bytes()).unwrap().len(), 2);
+        assert!(parse_pem_bundle(b"not a certificate").is_err());
+    }
+
+    #[test]
+    fn test_encode_pem_round_trips() {
+        let certs = parse_pem_bundle(TEST_CERT_PEM.as_bytes()).unwrap();
+        let pem = encode_pem(&certs);
+        assert_eq!(parse_pem_bundle(pem.as_bytes()).unwrap(), certs);
+        assert!(pem.lines().all(|line| line.len() <= 64));
+    }
+
+    #[test]
+    fn test_system_store_off_without_extra_cas_is_an_error() {
+        let settings = TlsSettings { use_system_store: false, ..Default::default() };
+        assert!(trusts_system_store(&settings, 0).is_err());
+        assert_eq!(trusts_system_store(&settings, 1), Ok(false));
+        assert_eq!(trusts_system_store(&TlsSettings::default(), 0), Ok(true));
+    }
+}
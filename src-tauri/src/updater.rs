@@ -8,8 +8,10 @@
 // 4. User clicks button → restart and apply update
 // 5. Or next app launch → update is automatically applied
 
+use crate::http_clients::{self, ClientPurpose};
 use crate::logger;
 use crate::proxy_config;
+use crate::tls_config;
 use serde::Serialize;
 use std::sync::atomic::{AtomicBool, Ordering};
 use tauri::{AppHandle, Emitter};
@@ -90,9 +92,35 @@ async fn check_and_download_silently(app: &AppHandle) -> Result<Option<String>,
 
     // Build updater with explicit target to override {{target}} template variable
     // Without this, tauri-plugin-updater only uses OS name (e.g., "darwin" instead of "darwin-aarch64")
-    let updater = app
-        .updater_builder()
-        .target(target.to_string())
+    let mut updater_builder = app.updater_builder().target(target.to_string());
+
+    // Apply the configured proxy mode (PAC evaluation may block, so resolve off the async runtime)
+    let endpoint = format!("https://download.myagents.io/update/{}.json", target);
+    let proxy = tauri::async_runtime::spawn_blocking(move || {
+        let url = reqwest::Url::parse(&endpoint).map_err(|e| e.to_string())?;
+        proxy_config::resolve_proxy_for_url(&url)
+    })
+    .await
+    .map_err(|e| format!("Failed to resolve update proxy: {}", e))?;
+    match proxy {
+        Ok(Some(proxy_url)) => {
+            let redacted = proxy_config::redact_proxy_url(&proxy_url);
+            logger::info(app, format!("[Updater] Using proxy: {}", redacted));
+            let proxy_url = reqwest::Url::parse(&proxy_url)
+                .map_err(|e| format!("Invalid proxy URL '{}': {}", redacted, e))?;
+            updater_builder = updater_builder.proxy(proxy_url);
+        }
+        Ok(None) => {}
+        Err(e) => logger::error(app, format!("[Updater] Invalid proxy configuration, checking without proxy: {}", e)),
+    }
+
+    // Trust the OS store and tlsSettings CAs (the plugin only bundles webpki roots)
+    updater_builder = updater_builder.configure_client(tls_config::updater_client_config()?);
+    if tls_config::read_tls_settings().insecure {
+        logger::warn(app, "[Updater] TLS certificate verification is disabled (tlsSettings.insecure)");
+    }
+
+    let updater = updater_builder
         .build()
         .map_err(|e| format!("Failed to build updater: {}", e))?;
     logger::info(
@@ -249,19 +277,18 @@ pub async fn test_update_connectivity(app: AppHandle) -> Result<String, String>
     let url = format!("https://download.myagents.io/update/{}.json", target);
     logger::info(&app, format!("[Updater] Testing HTTP connectivity to: {}", url));
 
-    // Build a reqwest client with user's proxy configuration
+    // Shared client with user's proxy configuration
     let current_version = app.package_info().version.to_string();
-    let builder = reqwest::Client::builder()
-        .user_agent(format!("MyAgents-Updater/{}", current_version))
-        .timeout(std::time::Duration::from_secs(30));
-
-    let client = proxy_config::build_client_with_proxy(builder)
+    let client = http_clients::client(&app, ClientPurpose::External)
         .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    http_clients::prepare_request(ClientPurpose::External, &url).await;
 
     // Make the request
-    let response = client
+    let request = client
         .get(&url)
-        .send()
+        .header(reqwest::header::USER_AGENT, format!("MyAgents-Updater/{}", current_version))
+        .timeout(std::time::Duration::from_secs(30));
+    let response = http_clients::send(&app, ClientPurpose::External, request)
         .await
         .map_err(|e| {
             let error_msg = format!(
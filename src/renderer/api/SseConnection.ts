@@ -6,15 +6,23 @@
  * 
  * Tauri mode:
  * - Rust SSE proxy supports multiple connections (keyed by tabId)
- * - Events are prefixed with tabId: sse:tabId:event-name
- * - Each Tab only receives events from its own connection
+ * - Events arrive as typed, ordered messages on a Channel owned by this webview,
+ *   so other windows never see this Tab's traffic
+ * - Several connections may subscribe to the same Tab (e.g. a detached viewer); they
+ *   share one upstream stream, and late subscribers receive recent events first
+ * - During a reply, the proxy reports a `stalled` message after runtimeSettings.sseStallTimeoutSecs
+ *   without events (heartbeats don't count); it is cleared by the next event
+ * - The stream is addressed by tabId only: Rust attaches it to the Tab's sidecar once
+ *   healthy and reattaches it after a sidecar restart (no port discovery here)
+ * - (The proxy's global `sse:tabId:event-name` events remain for callers without a channel)
+ * - With `replay`, the connection follows the Tab's recording replays (replay_sse_recording)
+ *   instead of its sidecar; no sidecar needs to be running
  * 
  * Browser mode (development):
  * - Uses native EventSource with full multiple connection support
  */
 
-import { invoke } from '@tauri-apps/api/core';
-import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { Channel, invoke } from '@tauri-apps/api/core';
 
 import { getTabServerUrl } from './tauriClient';
 import { isTauriEnvironment } from '../utils/browserMock';
@@ -72,6 +80,41 @@ const ALL_EVENTS = [...JSON_EVENTS, ...JSON_OR_STRING_EVENTS, ...STRING_EVENTS,
 
 export type SseEventHandler = (eventName: string, data: unknown) => void;
 export type SseConnectionStatusHandler = (status: 'connected' | 'disconnected' | 'reconnecting' | 'failed') => void;
+/** Called with the stall details, then with null once events flow again */
+export type SseStallHandler = (stall: SseStallInfo | null) => void;
+
+/** Payload of the Rust proxy's `sse:{tabId}:stalled` event */
+export interface SseStallInfo {
+    /** Time since the agent's last event */
+    silentMs: number;
+    /** Time since the sidecar's last heartbeat (null if none yet); recent = sidecar still alive */
+    lastHeartbeatMs: number | null;
+    heartbeats: number;
+}
+
+/** Payload of the Rust proxy's `sse:{tabId}:reconnecting` event */
+interface SseReconnectingInfo {
+    attempt: number;
+    maxAttempts: number;
+    delayMs: number;
+    error: string;
+    lastEventId: string | null;
+}
+
+/** One entry of the Rust proxy's `sse:{tabId}:batch` event (raw SSE data, in order) */
+interface SseBatchEntry {
+    event: string;
+    data: string;
+}
+
+/** Message on the Rust proxy's channel (see SseMessage in sse_proxy.rs) */
+type SseChannelMessage =
+    | { type: 'event'; event: string; data: string }
+    | { type: 'batch'; entries: SseBatchEntry[] }
+    | ({ type: 'reconnecting' } & SseReconnectingInfo)
+    | { type: 'reconnected'; attempts: number; lastEventId: string | null }
+    | { type: 'error'; message: string }
+    | ({ type: 'stalled' } & SseStallInfo);
 
 // Reconnection configuration
 const RECONNECT_MAX_ATTEMPTS = 3;
@@ -83,11 +126,17 @@ const RECONNECT_MAX_DELAY_MS = 10000;
  */
 export class SseConnection {
     private eventSource: EventSource | null = null;
-    private tauriUnlisteners: UnlistenFn[] = [];
+    private tauriChannel: Channel<SseChannelMessage> | null = null;
+    /** Our subscription to the Tab's stream in the Rust proxy */
+    private tauriSubscriberId: number | null = null;
     private tauriConnected = false;
     private eventHandler: SseEventHandler | null = null;
     private statusHandler: SseConnectionStatusHandler | null = null;
+    private stallHandler: SseStallHandler | null = null;
+    private stalled = false;
     private connectionId: string;
+    /** Subscribe to the Tab's replay stream instead of its sidecar (Tauri mode only) */
+    private replay: boolean;
 
     // Reconnection state
     private reconnectAttempts = 0;
@@ -95,8 +144,9 @@ export class SseConnection {
     private isReconnecting = false;
     private shouldReconnect = true; // Set to false when intentionally disconnecting
 
-    constructor(connectionId: string) {
+    constructor(connectionId: string, options?: { replay?: boolean }) {
         this.connectionId = connectionId;
+        this.replay = options?.replay ?? false;
     }
 
     /**
@@ -113,6 +163,22 @@ export class SseConnection {
         this.statusHandler = handler;
     }
 
+    /**
+     * Set the handler for reply stalls (Tauri mode only)
+     */
+    setStallHandler(handler: SseStallHandler): void {
+        this.stallHandler = handler;
+    }
+
+    /**
+     * Report a stall, or (null) that it is over
+     */
+    private notifyStall(stall: SseStallInfo | null): void {
+        if (stall === null && !this.stalled) return;
+        this.stalled = stall !== null;
+        this.stallHandler?.(stall);
+    }
+
     /**
      * Notify status change
      */
@@ -234,36 +300,21 @@ export class SseConnection {
     private async connectTauri(): Promise<void> {
         if (this.tauriConnected) return;
 
-        // Use Tab-specific server URL
-        const serverUrl = await getTabServerUrl(this.connectionId);
-        const sseUrl = `${serverUrl}/chat/stream`;
-
-        console.debug(`[SSE ${this.connectionId}] Connecting Tauri SSE proxy:`, sseUrl);
-
-        // Set up listeners for Tab-prefixed SSE event types
-        // Events are now: sse:tabId:chat:init, sse:tabId:chat:message-chunk, etc.
-        for (const eventName of ALL_EVENTS) {
-            // Listen for events with this Tab's prefix
-            const tauriEventName = `sse:${this.connectionId}:${eventName}`;
-            const unlisten = await listen<string>(tauriEventName, (event) => {
-                this.handleSseEvent(eventName, event.payload);
-            });
-            this.tauriUnlisteners.push(unlisten);
-        }
+        console.debug(`[SSE ${this.connectionId}] Connecting Tauri SSE proxy`);
 
-        // Listen for Tab-specific SSE proxy errors
-        const errorUnlisten = await listen<string>(`sse:${this.connectionId}:error`, (event) => {
-            console.error(`[SSE ${this.connectionId}] Proxy error:`, event.payload);
-            // Trigger reconnection on Tauri SSE errors
-            if (this.shouldReconnect && !this.isReconnecting) {
-                this.scheduleTauriReconnect();
-            }
-        });
-        this.tauriUnlisteners.push(errorUnlisten);
+        // Messages arrive in stream order, only in this webview
+        const channel = new Channel<SseChannelMessage>();
+        channel.onmessage = (message) => this.handleChannelMessage(message);
+        this.tauriChannel = channel;
 
-        // Start the Rust SSE proxy with Tab ID
+        // Subscribe to the Tab's sidecar-bound stream (fails if the sidecar isn't running),
+        // or to its replay stream
         try {
-            await invoke('start_sse_proxy', { url: sseUrl, tabId: this.connectionId });
+            this.tauriSubscriberId = await invoke<number>('start_sse_proxy', {
+                tabId: this.connectionId,
+                channel,
+                replay: this.replay,
+            });
             this.tauriConnected = true;
             this.reconnectAttempts = 0;
             this.isReconnecting = false;
@@ -271,10 +322,63 @@ export class SseConnection {
             console.debug(`[SSE ${this.connectionId}] Tauri SSE proxy started`);
         } catch (error) {
             console.error(`[SSE ${this.connectionId}] Failed to start Tauri SSE proxy:`, error);
+            this.detachChannel();
             throw error;
         }
     }
 
+    /**
+     * Dispatch one message from the Rust proxy's channel
+     */
+    private handleChannelMessage(message: SseChannelMessage): void {
+        switch (message.type) {
+            case 'event':
+                this.notifyStall(null);
+                this.handleSseEvent(message.event, message.data);
+                break;
+            case 'batch':
+                this.notifyStall(null);
+                // Delta events coalesced by the Rust proxy (runtimeSettings.sseBatchWindowMs),
+                // replayed in stream order through the same handler
+                for (const entry of message.entries) {
+                    this.handleSseEvent(entry.event, entry.data);
+                }
+                break;
+            case 'reconnecting':
+                // The Rust proxy reconnects on its own (resuming via Last-Event-ID);
+                // `error` below only arrives once it has given up
+                console.warn(`[SSE ${this.connectionId}] Proxy reconnecting (${message.attempt}/${message.maxAttempts}):`, message.error);
+                this.notifyStatus('reconnecting');
+                break;
+            case 'reconnected':
+                console.debug(`[SSE ${this.connectionId}] Proxy reconnected`);
+                this.notifyStatus('connected');
+                break;
+            case 'stalled':
+                console.warn(`[SSE ${this.connectionId}] No events for ${message.silentMs}ms, last heartbeat:`, message.lastHeartbeatMs);
+                this.notifyStall({ silentMs: message.silentMs, lastHeartbeatMs: message.lastHeartbeatMs, heartbeats: message.heartbeats });
+                break;
+            case 'error':
+                console.error(`[SSE ${this.connectionId}] Proxy error:`, message.message);
+                // Trigger reconnection on Tauri SSE errors
+                if (this.shouldReconnect && !this.isReconnecting) {
+                    this.scheduleTauriReconnect();
+                }
+                break;
+        }
+    }
+
+    /**
+     * Stop delivering messages from the current channel
+     */
+    private detachChannel(): void {
+        this.tauriSubscriberId = null;
+        if (this.tauriChannel) {
+            this.tauriChannel.onmessage = () => {};
+            this.tauriChannel = null;
+        }
+    }
+
     /**
      * Connect to SSE stream
      */
@@ -306,16 +410,12 @@ export class SseConnection {
         // Disconnect Tauri SSE proxy
         if (this.tauriConnected) {
             try {
-                await invoke('stop_sse_proxy', { tabId: this.connectionId });
+                await invoke('stop_sse_proxy', { tabId: this.connectionId, subscriberId: this.tauriSubscriberId, replay: this.replay });
             } catch (error) {
                 console.error(`[SSE ${this.connectionId}] Failed to stop Tauri SSE proxy:`, error);
             }
 
-            // Unregister all Tauri event listeners
-            for (const unlisten of this.tauriUnlisteners) {
-                unlisten();
-            }
-            this.tauriUnlisteners = [];
+            this.detachChannel();
             this.tauriConnected = false;
         }
 
@@ -414,14 +514,10 @@ export class SseConnection {
             try {
                 // Stop existing proxy
                 if (this.tauriConnected) {
-                    await invoke('stop_sse_proxy', { tabId: this.connectionId });
+                    await invoke('stop_sse_proxy', { tabId: this.connectionId, subscriberId: this.tauriSubscriberId, replay: this.replay });
                     this.tauriConnected = false;
                 }
-                // Clear listeners
-                for (const unlisten of this.tauriUnlisteners) {
-                    unlisten();
-                }
-                this.tauriUnlisteners = [];
+                this.detachChannel();
 
                 console.debug(`[SSE ${this.connectionId}] Attempting Tauri reconnection...`);
                 await this.connectTauri();
@@ -451,6 +547,6 @@ export class SseConnection {
 /**
  * Create a new SSE connection instance
  */
-export function createSseConnection(connectionId: string): SseConnection {
-    return new SseConnection(connectionId);
+export function createSseConnection(connectionId: string, options?: { replay?: boolean }): SseConnection {
+    return new SseConnection(connectionId, options);
 }
@@ -73,23 +73,11 @@ export async function apiGetJson<T>(endpoint: string): Promise<T> {
 
 /**
  * POST FormData to API endpoint (for file uploads)
- * 
- * WARNING: FormData uploads don't work in Tauri mode through proxyFetch.
- * This function only works in browser development mode.
- * For Tauri mode file uploads, use Tauri's native file dialog APIs.
+ * In Tauri mode the Rust proxy sends it as multipart/form-data with raw file bytes
  */
 export async function apiPostFormData<T>(endpoint: string, formData: FormData): Promise<T> {
-    if (isTauriEnvironment()) {
-        // FormData doesn't serialize properly through Tauri's proxyFetch
-        // Need to use Tauri's native file APIs for file uploads in desktop mode
-        throw new Error(
-            'FormData uploads are not supported in desktop mode. ' +
-            'Please use Tauri file dialog APIs for file operations.'
-        );
-    }
-
-    // Browser mode: use native fetch
-    const response = await fetch(endpoint, {
+    // Content-Type (with boundary) is set by fetch / the Rust proxy
+    const response = await apiFetch(endpoint, {
         method: 'POST',
         body: formData,
     });
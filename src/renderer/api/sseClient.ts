@@ -1,5 +1,4 @@
-import { invoke } from '@tauri-apps/api/core';
-import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { Channel, invoke } from '@tauri-apps/api/core';
 
 import { emitEvent } from './eventBus';
 import { getServerUrl } from './tauriClient';
@@ -40,8 +39,16 @@ const NULL_EVENTS = new Set(['chat:message-complete', 'chat:message-stopped']);
 // Browser EventSource for non-Tauri environments
 let eventSource: EventSource | null = null;
 
-// Tauri event listeners for SSE proxy
-let tauriUnlisteners: UnlistenFn[] = [];
+// Messages pushed by the Rust SSE proxy (subset of SseConnection's SseChannelMessage)
+type SseProxyMessage =
+  | { type: 'event'; event: string; data: string }
+  | { type: 'batch'; entries: { event: string; data: string }[] }
+  | { type: 'error'; message: string }
+  | { type: 'reconnecting' | 'reconnected' | 'stalled' };
+
+// Tauri SSE proxy subscription
+let tauriChannel: Channel<SseProxyMessage> | null = null;
+let tauriSubscriberId: number | null = null;
 let tauriSseConnected = false;
 
 function handleBrowserEvent(event: MessageEvent<string>): void {
@@ -81,31 +88,35 @@ async function connectTauriSseProxy(): Promise<void> {
 
   console.debug('[SSE] Using Tauri SSE proxy for:', sseUrl);
 
-  // Set up listeners for all SSE event types
-  const allEvents = [...JSON_EVENTS, ...STRING_EVENTS, ...NULL_EVENTS];
-
-  for (const eventName of allEvents) {
-    // Tauri events are prefixed with "sse:" and event name
-    const tauriEventName = `sse:${eventName}`;
-    const unlisten = await listen<string>(tauriEventName, (event) => {
-      handleSseEvent(eventName, event.payload);
-    });
-    tauriUnlisteners.push(unlisten);
-  }
-
-  // Also listen for SSE proxy errors
-  const errorUnlisten = await listen<string>('sse:error', (event) => {
-    console.error('[SSE Proxy] Error:', event.payload);
-  });
-  tauriUnlisteners.push(errorUnlisten);
+  // The proxy delivers events in stream order over this webview's channel
+  const channel = new Channel<SseProxyMessage>();
+  channel.onmessage = (message) => {
+    switch (message.type) {
+      case 'event':
+        handleSseEvent(message.event, message.data);
+        break;
+      case 'batch':
+        for (const entry of message.entries) {
+          handleSseEvent(entry.event, entry.data);
+        }
+        break;
+      case 'error':
+        console.error('[SSE Proxy] Error:', message.message);
+        break;
+      default:
+        break;
+    }
+  };
+  tauriChannel = channel;
 
   // Start the Rust SSE proxy
   try {
-    await invoke('start_sse_proxy', { url: sseUrl });
+    tauriSubscriberId = await invoke<number>('start_sse_proxy', { url: sseUrl, channel });
     tauriSseConnected = true;
     console.debug('[SSE] Tauri SSE proxy started');
   } catch (error) {
     console.error('[SSE] Failed to start Tauri SSE proxy:', error);
+    tauriChannel = null;
     throw error;
   }
 }
@@ -146,16 +157,17 @@ export async function disconnectSse(): Promise<void> {
   // Disconnect Tauri SSE proxy
   if (tauriSseConnected) {
     try {
-      await invoke('stop_sse_proxy');
+      await invoke('stop_sse_proxy', { subscriberId: tauriSubscriberId });
     } catch (error) {
       console.error('[SSE] Failed to stop Tauri SSE proxy:', error);
     }
 
-    // Unregister all Tauri event listeners
-    for (const unlisten of tauriUnlisteners) {
-      unlisten();
+    // Late messages are ignored once the channel is dropped
+    if (tauriChannel) {
+      tauriChannel.onmessage = () => {};
     }
-    tauriUnlisteners = [];
+    tauriChannel = null;
+    tauriSubscriberId = null;
     tauriSseConnected = false;
   }
 
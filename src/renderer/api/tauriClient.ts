@@ -1,7 +1,7 @@
 // Tauri IPC client for communicating with the Rust backend
 // Handles sidecar lifecycle and provides server URL for HTTP communication
 
-import { invoke } from '@tauri-apps/api/core';
+import { Channel, invoke } from '@tauri-apps/api/core';
 import { isBrowserDevMode, isTauriEnvironment } from '@/utils/browserMock';
 
 /** Sidecar status returned from Rust backend */
@@ -236,46 +236,187 @@ interface ProxyHttpResponse {
     is_base64: boolean;
 }
 
+/** Flatten any HeadersInit into a plain record for IPC */
+function toHeaderRecord(init?: HeadersInit): Record<string, string> {
+    const headers: Record<string, string> = {};
+    if (init) {
+        if (init instanceof Headers) {
+            init.forEach((value, key) => {
+                headers[key] = value;
+            });
+        } else if (Array.isArray(init)) {
+            init.forEach(([key, value]) => {
+                headers[key] = value;
+            });
+        } else {
+            Object.assign(headers, init);
+        }
+    }
+    return headers;
+}
+
+/** multipart/form-data field for the Rust proxy (file content as base64) */
+interface ProxyMultipartPart {
+    name: string;
+    value?: string;
+    data?: string;
+    filename?: string;
+    contentType?: string;
+}
+
+/** Request accepted by proxy_http_request / proxy_http_request_stream */
+interface ProxyHttpRequest {
+    url: string;
+    method: string;
+    body?: string;
+    /** 'base64' when body carries raw bytes */
+    bodyEncoding?: 'text' | 'base64';
+    headers: Record<string, string> | null;
+    multipart?: ProxyMultipartPart[];
+    timeoutMs?: number;
+    retries?: number;
+    retryOn?: ProxyRetryOn;
+}
+
+/** Which methods the Rust proxy may retry ('idempotent' by default) */
+export type ProxyRetryOn = 'idempotent' | 'always' | 'never';
+
+/** Per-request timeout and retry policy for proxyFetch */
+export interface ProxyRequestOptions {
+    /** Total timeout per attempt (default: runtimeSettings.httpProxyTimeoutSecs) */
+    timeoutMs?: number;
+    /** Extra attempts after connection failures or 502/503/504 (default 2, max 5) */
+    retries?: number;
+    retryOn?: ProxyRetryOn;
+}
+
+/** Structured failure returned by proxy_http_request */
+interface ProxyHttpErrorPayload {
+    /** 'forbidden': not a running sidecar or an httpProxyPolicy.allowedOrigins entry */
+    kind: 'request' | 'connect' | 'timeout' | 'status' | 'body' | 'cancelled' | 'forbidden';
+    message: string;
+    attempts: number;
+    status: number | null;
+}
+
+/** Error thrown by proxyFetch when the Rust proxy gives up */
+export class ProxyHttpError extends Error {
+    readonly kind: ProxyHttpErrorPayload['kind'];
+    readonly attempts: number;
+    readonly status: number | null;
+
+    constructor(payload: ProxyHttpErrorPayload) {
+        super(`[proxyFetch] ${payload.kind} error after ${payload.attempts} attempt(s): ${payload.message}`);
+        this.name = 'ProxyHttpError';
+        this.kind = payload.kind;
+        this.attempts = payload.attempts;
+        this.status = payload.status;
+    }
+}
+
+function isProxyHttpErrorPayload(value: unknown): value is ProxyHttpErrorPayload {
+    return typeof value === 'object' && value !== null && 'kind' in value && 'attempts' in value;
+}
+
+function bytesToBase64(bytes: Uint8Array): string {
+    // Chunked to stay below the argument limit of String.fromCharCode
+    let binary = '';
+    for (let i = 0; i < bytes.length; i += 0x8000) {
+        binary += String.fromCharCode(...bytes.subarray(i, i + 0x8000));
+    }
+    return btoa(binary);
+}
+
+function hasHeader(headers: Record<string, string>, name: string): boolean {
+    return Object.keys(headers).some((key) => key.toLowerCase() === name);
+}
+
+/**
+ * Convert fetch-style options into the proxy request: FormData becomes multipart,
+ * Blob / ArrayBuffer / typed arrays are sent as raw bytes (base64 over IPC)
+ */
+async function toProxyRequest(url: string, options?: RequestInit): Promise<ProxyHttpRequest> {
+    const headers = toHeaderRecord(options?.headers);
+    const request: ProxyHttpRequest = { url, method: options?.method || 'GET', headers: null };
+    const body = options?.body;
+
+    if (body instanceof FormData) {
+        request.multipart = await Promise.all([...body.entries()].map(async ([name, value]) => (
+            typeof value === 'string'
+                ? { name, value }
+                : {
+                    name,
+                    data: bytesToBase64(new Uint8Array(await value.arrayBuffer())),
+                    filename: value.name,
+                    contentType: value.type || undefined,
+                }
+        )));
+    } else if (body instanceof Blob) {
+        request.body = bytesToBase64(new Uint8Array(await body.arrayBuffer()));
+        request.bodyEncoding = 'base64';
+        if (body.type && !hasHeader(headers, 'content-type')) {
+            headers['Content-Type'] = body.type;
+        }
+    } else if (body instanceof ArrayBuffer || ArrayBuffer.isView(body)) {
+        const bytes = body instanceof ArrayBuffer
+            ? new Uint8Array(body)
+            : new Uint8Array(body.buffer, body.byteOffset, body.byteLength);
+        request.body = bytesToBase64(bytes);
+        request.bodyEncoding = 'base64';
+    } else if (body instanceof URLSearchParams) {
+        request.body = body.toString();
+        if (!hasHeader(headers, 'content-type')) {
+            headers['Content-Type'] = 'application/x-www-form-urlencoded;charset=UTF-8';
+        }
+    } else if (body instanceof ReadableStream) {
+        throw new Error('[proxyFetch] ReadableStream request bodies are not supported');
+    } else if (body) {
+        request.body = String(body);
+    }
+
+    request.headers = Object.keys(headers).length > 0 ? headers : null;
+    return request;
+}
+
+/**
+ * Abort an in-flight proxied request (proxyFetch / proxyFetchStream)
+ * @returns false if the request had already finished
+ */
+export async function cancelHttpRequest(requestId: string): Promise<boolean> {
+    if (!isTauri()) {
+        return false;
+    }
+    return invoke<boolean>('cancel_http_request', { requestId });
+}
+
 /**
  * Proxy HTTP request through Rust to bypass WebView CORS
  * Falls back to native fetch in browser mode
+ * Honours `options.signal`: aborting cancels the request in Rust
+ * `proxyOptions` sets the per-request timeout and retry policy; failures throw ProxyHttpError
  */
 export async function proxyFetch(
     url: string,
-    options?: RequestInit
+    options?: RequestInit,
+    proxyOptions?: ProxyRequestOptions
 ): Promise<Response> {
     // Browser mode: use native fetch (Vite proxy handles CORS)
     if (!isTauri()) {
         return fetch(url, options);
     }
 
-    const method = options?.method || 'GET';
-    const body = options?.body ? String(options.body) : undefined;
-
-    // Extract headers
-    const headers: Record<string, string> = {};
-    if (options?.headers) {
-        if (options.headers instanceof Headers) {
-            options.headers.forEach((value, key) => {
-                headers[key] = value;
-            });
-        } else if (Array.isArray(options.headers)) {
-            options.headers.forEach(([key, value]) => {
-                headers[key] = value;
-            });
-        } else {
-            Object.assign(headers, options.headers);
-        }
-    }
+    const signal = options?.signal;
+    signal?.throwIfAborted();
+    const requestId = crypto.randomUUID();
+    const onAbort = () => {
+        cancelHttpRequest(requestId).catch(() => {});
+    };
+    signal?.addEventListener('abort', onAbort, { once: true });
 
     try {
         const result = await invoke<ProxyHttpResponse>('proxy_http_request', {
-            request: {
-                url,
-                method,
-                body,
-                headers: Object.keys(headers).length > 0 ? headers : null,
-            }
+            request: { ...await toProxyRequest(url, options), ...proxyOptions },
+            requestId,
         });
 
         // Handle base64 encoded binary responses
@@ -298,11 +439,158 @@ export async function proxyFetch(
             headers: result.headers,
         });
     } catch (error) {
+        if (signal?.aborted) {
+            throw new DOMException('The operation was aborted.', 'AbortError');
+        }
         console.error('[proxyFetch] Error:', error);
-        throw error;
+        throw isProxyHttpErrorPayload(error) ? new ProxyHttpError(error) : error;
+    } finally {
+        signal?.removeEventListener('abort', onAbort);
     }
 }
 
+/** Messages pushed by the Rust streaming proxy over the request's channel, in order */
+type ProxyStreamMessage =
+    | { type: 'head'; status: number; headers: Record<string, string> }
+    | { type: 'chunk'; seq: number; data: string } // data: base64-encoded body bytes
+    | { type: 'end'; bytes: number; chunks: number }
+    | { type: 'error'; message: string; cancelled: boolean };
+
+/** Chunks the Rust side may send ahead of the reader (backpressure window) */
+const STREAM_CREDIT_WINDOW = 4;
+
+/** Statuses whose Response must not have a body */
+const NULL_BODY_STATUSES = new Set([101, 204, 205, 304]);
+
+function base64ToBytes(data: string): Uint8Array {
+    const binaryString = atob(data);
+    const bytes = new Uint8Array(binaryString.length);
+    for (let i = 0; i < binaryString.length; i++) {
+        bytes[i] = binaryString.charCodeAt(i);
+    }
+    return bytes;
+}
+
+/**
+ * Like proxyFetch, but the body is streamed from Rust in chunks instead of crossing
+ * IPC as one string. Use for large responses (file contents, images).
+ * Chunks are only requested as the body is read; aborting `options.signal` or
+ * cancelling the body cancels the request in Rust.
+ */
+export async function proxyFetchStream(
+    url: string,
+    options?: RequestInit
+): Promise<Response> {
+    if (!isTauri()) {
+        return fetch(url, options);
+    }
+
+    const signal = options?.signal;
+    signal?.throwIfAborted();
+    const requestId = crypto.randomUUID();
+    const channel = new Channel<ProxyStreamMessage>();
+    let onAbort = () => {};
+    // Late messages (after end, error or cancel) are ignored
+    const cleanup = () => {
+        channel.onmessage = () => {};
+        signal?.removeEventListener('abort', onAbort);
+    };
+    const ack = (count: number) => {
+        invoke('ack_http_chunks', { requestId, count }).catch(() => {});
+    };
+    const cancel = () => {
+        cleanup();
+        cancelHttpRequest(requestId).catch(() => {});
+    };
+
+    // Credits used by delivered chunks that have not been handed back yet
+    let owed = 0;
+    let controller!: ReadableStreamDefaultController<Uint8Array>;
+    const body = new ReadableStream<Uint8Array>({
+        start(c) {
+            controller = c;
+        },
+        pull() {
+            if (owed > 0) {
+                ack(owed);
+                owed = 0;
+            }
+        },
+        cancel,
+    }, { highWaterMark: STREAM_CREDIT_WINDOW });
+
+    return new Promise<Response>((resolve, reject) => {
+        let headReceived = false;
+        const fail = (error: Error) => {
+            cleanup();
+            if (headReceived) {
+                controller.error(error);
+            } else {
+                reject(error);
+            }
+        };
+
+        // Settle right away: Rust's cancelled `error` arrives after cleanup() muted the channel
+        onAbort = () => {
+            cancel();
+            fail(new DOMException('The operation was aborted.', 'AbortError'));
+        };
+        signal?.addEventListener('abort', onAbort, { once: true });
+
+        channel.onmessage = (message) => {
+            switch (message.type) {
+                case 'head':
+                    headReceived = true;
+                    resolve(new Response(
+                        NULL_BODY_STATUSES.has(message.status) ? null : body,
+                        { status: message.status, headers: message.headers },
+                    ));
+                    break;
+                case 'chunk':
+                    controller.enqueue(base64ToBytes(message.data));
+                    owed++;
+                    if ((controller.desiredSize ?? 0) > 0) {
+                        ack(owed);
+                        owed = 0;
+                    }
+                    break;
+                case 'end':
+                    cleanup();
+                    controller.close();
+                    break;
+                case 'error': {
+                    const error = new Error(message.message);
+                    error.name = message.cancelled ? 'AbortError' : 'Error';
+                    fail(error);
+                    break;
+                }
+            }
+        };
+
+        const start = async () => {
+            await invoke<string>('proxy_http_request_stream', {
+                request: await toProxyRequest(url, options),
+                requestId,
+                initialCredits: STREAM_CREDIT_WINDOW,
+                channel,
+            });
+            // Aborted before Rust registered the request: the first cancel missed it
+            if (signal?.aborted) {
+                cancelHttpRequest(requestId).catch(() => {});
+            }
+        };
+
+        start().catch((error) => {
+            console.error('[proxyFetchStream] Error:', error);
+            if (isProxyHttpErrorPayload(error)) {
+                fail(new ProxyHttpError(error));
+            } else {
+                fail(error instanceof Error ? error : new Error(String(error)));
+            }
+        });
+    });
+}
+
 /**
  * POST JSON through Rust proxy
  */
@@ -5,7 +5,7 @@
 import { Plus, Sparkles, Terminal, ChevronRight, Loader2, ChevronLeft } from 'lucide-react';
 import { useCallback, useEffect, useState, useRef, useMemo } from 'react';
 
-import { apiGetJson, apiPostJson } from '@/api/apiFetch';
+import { apiGetJson, apiPostFormData, apiPostJson } from '@/api/apiFetch';
 import { useToast } from '@/components/Toast';
 import SkillDetailPanel from './SkillDetailPanel';
 import type { SkillDetailPanelRef } from './SkillDetailPanel';
@@ -143,40 +143,29 @@ export default function GlobalSkillsPanel() {
 
     // 上传技能文件
     const handleUploadSkill = useCallback(async (file: File) => {
+        const formData = new FormData();
+        formData.append('file', file);
+        formData.append('scope', 'user');
         try {
-            const reader = new FileReader();
-            reader.onload = async () => {
-                const base64Content = (reader.result as string).split(',')[1];
-                try {
-                    const response = await apiPostJson<{
-                        success: boolean;
-                        folderName?: string;
-                        message?: string;
-                        error?: string;
-                    }>('/api/skill/upload', {
-                        filename: file.name,
-                        content: base64Content,
-                        scope: 'user'
-                    });
+            const response = await apiPostFormData<{
+                success: boolean;
+                folderName?: string;
+                message?: string;
+                error?: string;
+            }>('/api/skill/upload', formData);
 
-                    if (response.success) {
-                        toastRef.current.success(response.message || '技能导入成功');
-                        setShowNewSkillDialog(false);
-                        setRefreshKey(k => k + 1);
-                        if (response.folderName) {
-                            setViewState({ type: 'skill-detail', name: response.folderName });
-                        }
-                    } else {
-                        toastRef.current.error(response.error || '导入失败');
-                    }
-                } catch {
-                    toastRef.current.error('导入失败');
+            if (response.success) {
+                toastRef.current.success(response.message || '技能导入成功');
+                setShowNewSkillDialog(false);
+                setRefreshKey(k => k + 1);
+                if (response.folderName) {
+                    setViewState({ type: 'skill-detail', name: response.folderName });
                 }
-            };
-            reader.onerror = () => toastRef.current.error('读取文件失败');
-            reader.readAsDataURL(file);
+            } else {
+                toastRef.current.error(response.error || '导入失败');
+            }
         } catch {
-            toastRef.current.error('上传失败');
+            toastRef.current.error('导入失败');
         }
     }, []);
 
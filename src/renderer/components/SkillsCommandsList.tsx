@@ -7,7 +7,7 @@
 import { Plus, Sparkles, Terminal, ChevronRight, Loader2 } from 'lucide-react';
 import { useCallback, useEffect, useState, useMemo, useRef } from 'react';
 
-import { apiGetJson as globalApiGet, apiPostJson as globalApiPost, apiDelete as globalApiDelete } from '@/api/apiFetch';
+import { apiGetJson as globalApiGet, apiPostJson as globalApiPost, apiPostFormData as globalApiPostForm, apiDelete as globalApiDelete } from '@/api/apiFetch';
 import { useTabStateOptional } from '@/context/TabContext';
 import { useToast } from '@/components/Toast';
 import ConfirmDialog from '@/components/ConfirmDialog';
@@ -47,9 +47,10 @@ export default function SkillsCommandsList({
 
     const api = useMemo(() => {
         if (apiGet && apiPost && apiDeleteFn) {
-            return { get: apiGet, post: apiPost, delete: apiDeleteFn };
+            // Tab apiPost sends FormData as multipart
+            return { get: apiGet, post: apiPost, postForm: apiPost, delete: apiDeleteFn };
         }
-        return { get: globalApiGet, post: globalApiPost, delete: globalApiDelete };
+        return { get: globalApiGet, post: globalApiPost, postForm: globalApiPostForm, delete: globalApiDelete };
     }, [apiGet, apiPost, apiDeleteFn]);
 
     // Track if we're in tab context (stable boolean that won't change)
@@ -119,46 +120,33 @@ export default function SkillsCommandsList({
 
     // 上传技能文件
     const handleUploadSkill = useCallback(async (file: File) => {
+        // 以 multipart 上传原始文件字节
+        const formData = new FormData();
+        formData.append('file', file);
+        formData.append('scope', scope);
         try {
-            // 读取文件为 base64
-            const reader = new FileReader();
-            reader.onload = async () => {
-                const base64Content = (reader.result as string).split(',')[1]; // 去除 data:xxx;base64, 前缀
-                try {
-                    const response = await api.post<{
-                        success: boolean;
-                        folderName?: string;
-                        message?: string;
-                        error?: string;
-                    }>('/api/skill/upload', {
-                        filename: file.name,
-                        content: base64Content,
-                        scope
-                    });
-
-                    if (response.success) {
-                        toastRef.current.success(response.message || '技能导入成功');
-                        setShowNewSkillDialog(false);
-                        loadData();
-                        // 进入新创建的技能详情页
-                        if (response.folderName) {
-                            onSelectSkill(response.folderName, scope, true);
-                        }
-                        // Notify SimpleChatInput to refresh slash commands
-                        window.dispatchEvent(new CustomEvent(CUSTOM_EVENTS.SKILL_COPIED_TO_PROJECT, { detail: { skillName: response.folderName } }));
-                    } else {
-                        toastRef.current.error(response.error || '导入失败');
-                    }
-                } catch {
-                    toastRef.current.error('导入失败');
+            const response = await api.postForm<{
+                success: boolean;
+                folderName?: string;
+                message?: string;
+                error?: string;
+            }>('/api/skill/upload', formData);
+
+            if (response.success) {
+                toastRef.current.success(response.message || '技能导入成功');
+                setShowNewSkillDialog(false);
+                loadData();
+                // 进入新创建的技能详情页
+                if (response.folderName) {
+                    onSelectSkill(response.folderName, scope, true);
                 }
-            };
-            reader.onerror = () => {
-                toastRef.current.error('读取文件失败');
-            };
-            reader.readAsDataURL(file);
+                // Notify SimpleChatInput to refresh slash commands
+                window.dispatchEvent(new CustomEvent(CUSTOM_EVENTS.SKILL_COPIED_TO_PROJECT, { detail: { skillName: response.folderName } }));
+            } else {
+                toastRef.current.error(response.error || '导入失败');
+            }
         } catch {
-            toastRef.current.error('上传失败');
+            toastRef.current.error('导入失败');
         }
     }, [scope, loadData, onSelectSkill, api]);
 
@@ -169,6 +169,15 @@ export function isVerifyExpired(verifiedAt: string): boolean {
  */
 export type ProxyProtocol = 'http' | 'socks5';
 
+/**
+ * Network proxy mode
+ * - direct: never use a proxy
+ * - manual: protocol://host:port below
+ * - system: HTTP(S)_PROXY / ALL_PROXY / NO_PROXY environment variables
+ * - pac: PAC script (file path or http(s) URL) evaluated per destination
+ */
+export type ProxyMode = 'direct' | 'manual' | 'system' | 'pac';
+
 /**
  * Network proxy default values
  */
@@ -192,9 +201,48 @@ export function isValidProxyHost(host: string): boolean {
  */
 export interface ProxySettings {
   enabled: boolean;
+  mode?: ProxyMode; // Default: 'manual'
   protocol: ProxyProtocol;
   host: string;
   port: number;
+  pacUrl?: string; // Required when mode is 'pac'
+  username?: string; // Proxy auth; the password is stored separately (cmd_set_proxy_password)
+  bypass?: string[]; // Direct destinations: domains, *.suffix, IPs, CIDRs (loopback is always direct)
+}
+
+/**
+ * TLS trust settings (see src-tauri/src/tls_config.rs)
+ */
+export interface TlsSettings {
+  caBundles?: string[]; // Extra PEM bundle paths (~/ is expanded)
+  useSystemStore?: boolean; // Default: true
+  insecure?: boolean; // Disables certificate verification, debugging only
+}
+
+/**
+ * Result of the test_proxy_connectivity command
+ * errorKind: config | dns | refused | timeout | unreachable | auth | proxy_rejected | tls_interception | tls | http
+ */
+export interface ProxyTestStage {
+  stage: 'config' | 'dns' | 'tcp' | 'proxyTls' | 'handshake' | 'tls' | 'http';
+  ok: boolean;
+  durationMs: number;
+  detail: string;
+  errorKind?: string | null;
+}
+
+export interface ProxyTestTarget {
+  url: string;
+  via: string; // Redacted proxy URL or 'direct'
+  ok: boolean;
+  errorKind?: string | null;
+  stages: ProxyTestStage[];
+}
+
+export interface ProxyTestReport {
+  mode: ProxyMode;
+  ok: boolean;
+  targets: ProxyTestTarget[];
 }
 
 /**
@@ -233,6 +281,42 @@ export interface AppConfig {
   // ===== Network Proxy (Developer) =====
   // HTTP/SOCKS5 proxy settings for external network requests
   proxySettings?: ProxySettings;
+
+  // ===== TLS Certificates (Developer) =====
+  // Extra CAs / OS store / insecure mode for outbound HTTPS (Rust clients and sidecars)
+  tlsSettings?: TlsSettings;
+
+  // ===== Runtime Tuning (Developer) =====
+  // Read by the Rust host only (see src-tauri/src/app_config.rs for defaults and valid ranges)
+  runtimeSettings?: RuntimeSettings;
+
+  // ===== HTTP Proxy Policy (Developer) =====
+  // Origins the Rust HTTP proxy may fetch besides running sidecars (see src-tauri/src/http_policy.rs)
+  httpProxyPolicy?: HttpProxyPolicy;
+}
+
+/**
+ * Extra origins for the Rust HTTP proxy, e.g. 'https://api.example.com' (bare hosts mean https)
+ */
+export interface HttpProxyPolicy {
+  allowedOrigins?: string[];
+}
+
+/**
+ * Sidecar and proxy runtime tuning; every field is optional and falls back to its default
+ */
+export interface RuntimeSettings {
+  healthCheckMaxAttempts?: number;      // 100ms apart, default 60 (10-3000)
+  gracefulShutdownTimeoutSecs?: number; // default 5 (1-60)
+  basePort?: number;                    // default 31415 (1024-65535)
+  portRange?: number;                   // default 500 (1-10000)
+  sseReadTimeoutSecs?: number;          // default 60 (20-3600)
+  httpProxyTimeoutSecs?: number;        // default 120 (5-3600)
+  sidecarRestartMaxAttempts?: number;   // default 5 (0-100, 0 disables auto restart)
+  sidecarRestartWindowSecs?: number;    // default 300 (10-86400)
+  sseBatchWindowMs?: number;            // default 16 (0-1000, 0 disables delta batching)
+  sseRecordingEnabled?: boolean;        // default false; records SSE streams to ~/.myagents/recordings
+  sseStallTimeoutSecs?: number;         // default 30 (0-3600, 0 disables); silence during a reply before "stalled"
 }
 
 /**
@@ -20,6 +20,7 @@ import type { SystemInitInfo } from '../../shared/types/system';
 import type { PermissionMode } from '@/config/types';
 import type { PermissionRequest } from '@/components/PermissionPrompt';
 import type { AskUserQuestionRequest } from '../../shared/types/askUserQuestion';
+import type { SseStallInfo } from '@/api/SseConnection';
 
 export type SessionState = 'idle' | 'running' | 'error';
 
@@ -84,6 +85,8 @@ export interface TabContextValue extends TabState {
 
     // SSE connection management
     isConnected: boolean;
+    isReconnecting: boolean; // SSE stream dropped and is being re-established
+    agentStall: SseStallInfo | null; // Reply in progress but no agent events for a while
     connectSse: () => Promise<void>;
     disconnectSse: () => void;
 
@@ -126,6 +129,8 @@ const defaultContextValue: TabContextValue = {
     pendingAskUserQuestion: null,
     toolCompleteCount: 0,
     isConnected: false,
+    isReconnecting: false,
+    agentStall: null,
     setMessages: () => { },
     setIsLoading: () => { },
     setSessionState: () => { },
@@ -14,7 +14,7 @@ import { flushSync } from 'react-dom';
 import type { ReactNode } from 'react';
 
 import { track } from '@/analytics';
-import { createSseConnection, type SseConnection } from '@/api/SseConnection';
+import { createSseConnection, type SseConnection, type SseStallInfo } from '@/api/SseConnection';
 import type { ImageAttachment } from '@/components/SimpleChatInput';
 import type { PermissionRequest } from '@/components/PermissionPrompt';
 import type { AskUserQuestionRequest, AskUserQuestion } from '../../shared/types/askUserQuestion';
@@ -25,7 +25,7 @@ import type { SystemInitInfo } from '../../shared/types/system';
 import type { LogEntry } from '@/types/log';
 import { parsePartialJson } from '@/utils/parsePartialJson';
 import { REACT_LOG_EVENT } from '@/utils/frontendLogger';
-import { getTabServerUrl, proxyFetch, stopTabSidecar, isTauri } from '@/api/tauriClient';
+import { getTabServerUrl, proxyFetch, proxyFetchStream, stopTabSidecar, isTauri } from '@/api/tauriClient';
 import type { PermissionMode } from '@/config/types';
 
 // File-modifying tools that should trigger workspace refresh
@@ -98,6 +98,12 @@ async function handleApiResponse<T>(response: Response): Promise<T> {
     return (await response.json()) as T;
 }
 
+// Endpoints with large responses (base64 file contents); streamed instead of
+// crossing IPC as a single string, which freezes the WebView for big images
+const STREAMED_RESPONSE_PATHS = new Set([
+    '/api/files/read-as-base64',
+]);
+
 /**
  * Create a Tab-scoped POST function
  */
@@ -105,7 +111,12 @@ function createPostJson(tabId: string) {
     return async <T,>(path: string, body?: unknown): Promise<T> => {
         const baseUrl = await getTabServerUrl(tabId);
         const url = `${baseUrl}${path}`;
-        const response = await proxyFetch(url, {
+        const fetchFn = STREAMED_RESPONSE_PATHS.has(path) ? proxyFetchStream : proxyFetch;
+        // FormData goes out as multipart (file uploads); everything else as JSON
+        const response = await fetchFn(url, body instanceof FormData ? {
+            method: 'POST',
+            body,
+        } : {
             method: 'POST',
             headers: { 'Content-Type': 'application/json' },
             body: body ? JSON.stringify(body) : undefined
@@ -180,6 +191,8 @@ export default function TabProvider({
     const [agentError, setAgentError] = useState<string | null>(null);
     const [systemStatus, setSystemStatus] = useState<string | null>(null);  // e.g., 'compacting'
     const [isConnected, setIsConnected] = useState(false);
+    const [isReconnecting, setIsReconnecting] = useState(false);
+    const [agentStall, setAgentStall] = useState<SseStallInfo | null>(null);
     const [pendingPermission, setPendingPermission] = useState<PermissionRequest | null>(null);
     const [pendingAskUserQuestion, setPendingAskUserQuestion] = useState<AskUserQuestionRequest | null>(null);
     const [toolCompleteCount, setToolCompleteCount] = useState(0);
@@ -968,6 +981,8 @@ export default function TabProvider({
 
         const sse = createSseConnection(tabId);
         sse.setEventHandler(handleSseEvent);
+        sse.setStatusHandler((status) => setIsReconnecting(status === 'reconnecting'));
+        sse.setStallHandler(setAgentStall);
         sseRef.current = sse;
 
         try {
@@ -987,6 +1002,8 @@ export default function TabProvider({
             void sseRef.current.disconnect();
             sseRef.current = null;
             setIsConnected(false);
+            setIsReconnecting(false);
+            setAgentStall(null);
         }
     }, []);
 
@@ -1273,6 +1290,8 @@ export default function TabProvider({
         pendingAskUserQuestion,
         toolCompleteCount,
         isConnected,
+        isReconnecting,
+        agentStall,
         setMessages,
         setIsLoading,
         setSessionState,
@@ -1296,7 +1315,7 @@ export default function TabProvider({
         respondAskUserQuestion,
     }), [
         tabId, agentDir, currentSessionId, messages, isLoading, sessionState,
-        logs, unifiedLogs, systemInitInfo, agentError, systemStatus, isActive, pendingPermission, pendingAskUserQuestion, toolCompleteCount, isConnected,
+        logs, unifiedLogs, systemInitInfo, agentError, systemStatus, isActive, pendingPermission, pendingAskUserQuestion, toolCompleteCount, isConnected, isReconnecting, agentStall,
         appendLog, appendUnifiedLog, clearUnifiedLogs, connectSse, disconnectSse, sendMessage, stopResponse, loadSession, resetSession,
         apiGetJson, postJson, apiPutJson, apiDeleteJson, respondPermission, respondAskUserQuestion
     ]);
@@ -1,4 +1,4 @@
-import { AlertTriangle, ArrowLeft, History, Plus, PanelRightOpen } from 'lucide-react';
+import { AlertTriangle, ArrowLeft, History, Hourglass, Loader2, Plus, PanelRightOpen } from 'lucide-react';
 import { useCallback, useEffect, useRef, useState } from 'react';
 
 import { track } from '@/analytics';
@@ -43,6 +43,8 @@ export default function Chat({ onBack, onNewSession }: ChatProps) {
     agentError,
     systemStatus,
     isActive,
+    isReconnecting,
+    agentStall,
     pendingPermission,
     pendingAskUserQuestion,
     toolCompleteCount,
@@ -542,6 +544,29 @@ export default function Chat({ onBack, onNewSession }: ChatProps) {
             subtitle="非图片文件将复制到 myagents_files 并自动引用"
           />
 
+          {isReconnecting && (
+            <div className="flex-shrink-0 border-b border-[var(--line)] bg-[var(--paper-contrast)] px-4 py-2 text-[11px] text-[var(--ink-muted)]">
+              <div className="mx-auto flex max-w-3xl items-center gap-2">
+                <Loader2 className="h-3.5 w-3.5 flex-shrink-0 animate-spin" />
+                <span>连接已中断，正在重新连接…（恢复后会补齐中断期间的输出）</span>
+              </div>
+            </div>
+          )}
+          {agentStall && !isReconnecting && (
+            <div className="flex-shrink-0 border-b border-[var(--line)] bg-[var(--paper-contrast)] px-4 py-2 text-[11px] text-[var(--ink-muted)]">
+              <div className="mx-auto flex max-w-3xl items-center gap-2">
+                <Hourglass className="h-3.5 w-3.5 flex-shrink-0" />
+                <span>
+                  Agent 已 {Math.round(agentStall.silentMs / 1000)} 秒没有新输出
+                  {/* Sidecar heartbeats every 15s: a recent one means only the agent is silent */}
+                  {agentStall.lastHeartbeatMs !== null && agentStall.lastHeartbeatMs < 30_000
+                    ? '（后台服务正常，模型可能仍在思考）'
+                    : '（后台服务也没有响应，可能已卡住）'}
+                  ，可以继续等待或停止本次回复
+                </span>
+              </div>
+            </div>
+          )}
           {agentError && (
             <div className="flex-shrink-0 border-b border-[var(--line)] bg-[#f5e4d9]/80 px-4 py-2 text-[11px] text-[var(--ink)]">
               <div className="mx-auto flex max-w-3xl items-start gap-2">
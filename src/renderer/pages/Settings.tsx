@@ -21,6 +21,9 @@ import {
     isVerifyExpired,
     SUBSCRIPTION_PROVIDER_ID,
     PROXY_DEFAULTS,
+    type ProxyMode,
+    type ProxySettings,
+    type ProxyTestReport,
     isValidProxyHost,
 } from '@/config/types';
 import {
@@ -290,6 +293,58 @@ export default function Settings({ initialSection, onSectionChange }: SettingsPr
     const [remoteVersion, setRemoteVersion] = useState<string>('');
     const [updateError, setUpdateError] = useState<string>('');
 
+    // Proxy password (stored by the Rust host in ~/.myagents/credentials.json, never in config)
+    const [proxyPassword, setProxyPassword] = useState('');
+    const [hasProxyPassword, setHasProxyPassword] = useState(false);
+    useEffect(() => {
+        if (!isTauriEnvironment()) return;
+        import('@tauri-apps/api/core')
+            .then(({ invoke }) => invoke('cmd_has_proxy_password') as Promise<boolean>)
+            .then(setHasProxyPassword)
+            .catch(() => setHasProxyPassword(false));
+    }, []);
+
+    const handleSaveProxyPassword = useCallback(async (password: string | null) => {
+        if (!isTauriEnvironment()) {
+            toast.error('此功能仅在桌面应用中可用');
+            return;
+        }
+        try {
+            const { invoke } = await import('@tauri-apps/api/core');
+            await invoke('cmd_set_proxy_password', { password });
+            setHasProxyPassword(!!password);
+            setProxyPassword('');
+            toast.success(password ? '代理密码已保存' : '代理密码已清除');
+        } catch (err) {
+            toast.error(`保存代理密码失败: ${err}`);
+        }
+    }, [toast]);
+
+    // Proxy connectivity test (runs against the current, possibly unsaved, settings)
+    const [proxyTest, setProxyTest] = useState<ProxyTestReport | null>(null);
+    const [proxyTesting, setProxyTesting] = useState(false);
+    const handleTestProxy = useCallback(async (settings: ProxySettings) => {
+        if (!isTauriEnvironment()) {
+            toast.error('此功能仅在桌面应用中可用');
+            return;
+        }
+        setProxyTesting(true);
+        setProxyTest(null);
+        try {
+            const { invoke } = await import('@tauri-apps/api/core');
+            const report = await invoke('test_proxy_connectivity', {
+                settings,
+                password: proxyPassword || null,
+                targets: null,
+            }) as ProxyTestReport;
+            setProxyTest(report);
+        } catch (err) {
+            toast.error(`代理测试失败: ${err}`);
+        } finally {
+            setProxyTesting(false);
+        }
+    }, [proxyPassword, toast]);
+
     // Check for updates (fetch remote version info)
     const handleCheckUpdate = useCallback(async () => {
         if (!isTauriEnvironment()) {
@@ -1448,6 +1503,7 @@ export default function Settings({ initialSection, onSectionChange }: SettingsPr
                                                         const current = config.proxySettings;
                                                         updateConfig({
                                                             proxySettings: {
+                                                                ...current,
                                                                 enabled: !current?.enabled,
                                                                 protocol: current?.protocol || PROXY_DEFAULTS.protocol,
                                                                 host: current?.host || PROXY_DEFAULTS.host,
@@ -1466,6 +1522,55 @@ export default function Settings({ initialSection, onSectionChange }: SettingsPr
                                             {/* Proxy settings form */}
                                             {config.proxySettings?.enabled && (
                                                 <div className="space-y-3 border-t border-[var(--line)] pt-4">
+                                                    {/* Mode */}
+                                                    <div className="flex items-center gap-3">
+                                                        <label className="w-16 text-xs text-[var(--ink-muted)]">模式</label>
+                                                        <select
+                                                            value={config.proxySettings?.mode || 'manual'}
+                                                            onChange={(e) => {
+                                                                updateConfig({
+                                                                    proxySettings: {
+                                                                        ...config.proxySettings!,
+                                                                        mode: e.target.value as ProxyMode,
+                                                                    }
+                                                                });
+                                                            }}
+                                                            className="flex-1 rounded-lg border border-[var(--line)] bg-[var(--paper)] px-3 py-1.5 text-xs text-[var(--ink)] focus:border-[var(--accent)] focus:outline-none"
+                                                        >
+                                                            <option value="manual">手动</option>
+                                                            <option value="system">系统（环境变量）</option>
+                                                            <option value="pac">PAC 脚本</option>
+                                                            <option value="direct">直连</option>
+                                                        </select>
+                                                    </div>
+
+                                                    {(config.proxySettings?.mode || 'manual') === 'system' && (
+                                                        <p className="text-xs text-[var(--ink-muted)]">
+                                                            使用 HTTP_PROXY / HTTPS_PROXY / ALL_PROXY / NO_PROXY 环境变量
+                                                        </p>
+                                                    )}
+
+                                                    {config.proxySettings?.mode === 'pac' && (
+                                                        <div className="flex items-center gap-3">
+                                                            <label className="w-16 text-xs text-[var(--ink-muted)]">PAC 地址</label>
+                                                            <input
+                                                                type="text"
+                                                                value={config.proxySettings?.pacUrl || ''}
+                                                                onChange={(e) => {
+                                                                    updateConfig({
+                                                                        proxySettings: {
+                                                                            ...config.proxySettings!,
+                                                                            pacUrl: e.target.value.trim(),
+                                                                        }
+                                                                    });
+                                                                }}
+                                                                placeholder="http://wpad.corp/proxy.pac 或本地文件路径"
+                                                                className="flex-1 rounded-lg border border-[var(--line)] bg-[var(--paper)] px-3 py-1.5 text-xs text-[var(--ink)] placeholder:text-[var(--ink-faint)] focus:border-[var(--accent)] focus:outline-none"
+                                                            />
+                                                        </div>
+                                                    )}
+
+                                                    {(config.proxySettings?.mode || 'manual') === 'manual' && (<>
                                                     {/* Protocol */}
                                                     <div className="flex items-center gap-3">
                                                         <label className="w-16 text-xs text-[var(--ink-muted)]">协议</label>
@@ -1552,13 +1657,177 @@ export default function Settings({ initialSection, onSectionChange }: SettingsPr
                                                             {config.proxySettings?.protocol || PROXY_DEFAULTS.protocol}://{config.proxySettings?.host || PROXY_DEFAULTS.host}:{config.proxySettings?.port || PROXY_DEFAULTS.port}
                                                         </code>
                                                     </div>
+                                                    </>)}
+
+                                                    {(config.proxySettings?.mode || 'manual') !== 'direct' && (<>
+                                                    {/* Username */}
+                                                    <div className="flex items-center gap-3">
+                                                        <label className="w-16 text-xs text-[var(--ink-muted)]">用户名</label>
+                                                        <input
+                                                            type="text"
+                                                            value={config.proxySettings?.username || ''}
+                                                            onChange={(e) => {
+                                                                updateConfig({
+                                                                    proxySettings: {
+                                                                        ...config.proxySettings!,
+                                                                        username: e.target.value.trim() || undefined,
+                                                                    }
+                                                                });
+                                                            }}
+                                                            placeholder="可选，代理需要认证时填写"
+                                                            className="flex-1 rounded-lg border border-[var(--line)] bg-[var(--paper)] px-3 py-1.5 text-xs text-[var(--ink)] placeholder:text-[var(--ink-faint)] focus:border-[var(--accent)] focus:outline-none"
+                                                        />
+                                                    </div>
+
+                                                    {/* Password (kept out of config.json) */}
+                                                    {config.proxySettings?.username && (
+                                                        <div className="flex items-center gap-3">
+                                                            <label className="w-16 text-xs text-[var(--ink-muted)]">密码</label>
+                                                            <input
+                                                                type="password"
+                                                                value={proxyPassword}
+                                                                onChange={(e) => setProxyPassword(e.target.value)}
+                                                                placeholder={hasProxyPassword ? '已保存，输入新密码以替换' : '未设置'}
+                                                                className="flex-1 rounded-lg border border-[var(--line)] bg-[var(--paper)] px-3 py-1.5 text-xs text-[var(--ink)] placeholder:text-[var(--ink-faint)] focus:border-[var(--accent)] focus:outline-none"
+                                                            />
+                                                            <button
+                                                                onClick={() => void handleSaveProxyPassword(proxyPassword || null)}
+                                                                disabled={!proxyPassword}
+                                                                className="rounded-lg bg-[var(--button-primary-bg)] px-3 py-1.5 text-xs font-medium text-[var(--button-primary-text)] transition-colors hover:bg-[var(--button-primary-bg-hover)] disabled:opacity-50"
+                                                            >
+                                                                保存
+                                                            </button>
+                                                            {hasProxyPassword && (
+                                                                <button
+                                                                    onClick={() => void handleSaveProxyPassword(null)}
+                                                                    className="rounded-lg px-2 py-1.5 text-xs text-[var(--ink-muted)] transition-colors hover:text-[var(--error)]"
+                                                                >
+                                                                    清除
+                                                                </button>
+                                                            )}
+                                                        </div>
+                                                    )}
+
+                                                    {/* Bypass list */}
+                                                    <div className="flex items-start gap-3">
+                                                        <label className="w-16 pt-1.5 text-xs text-[var(--ink-muted)]">不走代理</label>
+                                                        <textarea
+                                                            rows={3}
+                                                            defaultValue={(config.proxySettings?.bypass || []).join('\n')}
+                                                            onBlur={(e) => {
+                                                                const bypass = e.target.value
+                                                                    .split(/[\n,]/)
+                                                                    .map((entry) => entry.trim())
+                                                                    .filter(Boolean);
+                                                                updateConfig({
+                                                                    proxySettings: {
+                                                                        ...config.proxySettings!,
+                                                                        bypass,
+                                                                    }
+                                                                });
+                                                            }}
+                                                            placeholder={'每行一个：gateway.corp.com、*.intranet、10.0.0.0/8'}
+                                                            className="flex-1 resize-none rounded-lg border border-[var(--line)] bg-[var(--paper)] px-3 py-1.5 font-mono text-xs text-[var(--ink)] placeholder:text-[var(--ink-faint)] focus:border-[var(--accent)] focus:outline-none"
+                                                        />
+                                                    </div>
+                                                    </>)}
+
+                                                    {/* Connectivity test */}
+                                                    <div className="flex items-center gap-3">
+                                                        <button
+                                                            onClick={() => void handleTestProxy(config.proxySettings!)}
+                                                            disabled={proxyTesting}
+                                                            className="flex items-center gap-1.5 rounded-lg border border-[var(--line)] px-3 py-1.5 text-xs text-[var(--ink)] transition-colors hover:bg-[var(--paper-contrast)] disabled:opacity-50"
+                                                        >
+                                                            {proxyTesting && <Loader2 className="h-3 w-3 animate-spin" />}
+                                                            测试连接
+                                                        </button>
+                                                        {proxyTest && (
+                                                            <span className={`text-xs ${proxyTest.ok ? 'text-[var(--success)]' : 'text-[var(--error)]'}`}>
+                                                                {proxyTest.ok ? '全部连通' : '存在连接失败'}
+                                                            </span>
+                                                        )}
+                                                    </div>
+                                                    {proxyTest && (
+                                                        <div className="space-y-2">
+                                                            {proxyTest.targets.map((target) => (
+                                                                <div key={target.url} className="rounded-lg bg-[var(--paper)] px-3 py-2 text-[11px]">
+                                                                    <div className="flex items-center gap-1.5 text-[var(--ink)]">
+                                                                        {target.ok
+                                                                            ? <Check className="h-3 w-3 text-[var(--success)]" />
+                                                                            : <X className="h-3 w-3 text-[var(--error)]" />}
+                                                                        <span className="font-mono">{target.url}</span>
+                                                                        <span className="text-[var(--ink-faint)]">via {target.via}</span>
+                                                                    </div>
+                                                                    {target.stages.map((stage) => (
+                                                                        <div key={stage.stage} className="ml-4 flex gap-2 text-[var(--ink-muted)]">
+                                                                            <span className="w-16 font-mono">{stage.stage}</span>
+                                                                            <span className="w-12 text-right">{stage.durationMs}ms</span>
+                                                                            <span className={stage.ok ? '' : 'text-[var(--error)]'}>{stage.detail}</span>
+                                                                        </div>
+                                                                    ))}
+                                                                </div>
+                                                            ))}
+                                                        </div>
+                                                    )}
 
                                                     <p className="text-[10px] text-[var(--ink-faint)]">
-                                                        注意：修改后需要重启应用或切换标签页才能生效
+                                                        修改后自动生效，运行中的标签页会自动重启；密码单独保存在 ~/.myagents/credentials.json
                                                     </p>
                                                 </div>
                                             )}
                                         </div>
+
+                                        {/* TLS Certificate Settings */}
+                                        <div className="rounded-xl border border-[var(--line)] bg-[var(--paper-contrast)] p-5">
+                                            <h3 className="mb-3 text-sm font-medium text-[var(--ink)]">TLS 证书</h3>
+                                            <p className="mb-4 text-xs text-[var(--ink-muted)]">
+                                                公司网络使用 HTTPS 解密代理时，添加其根证书以便更新检查和 Agent 正常连接
+                                            </p>
+                                            <div className="space-y-3">
+                                                <div className="flex items-start gap-3">
+                                                    <label className="w-16 pt-1.5 text-xs text-[var(--ink-muted)]">CA 证书</label>
+                                                    <textarea
+                                                        rows={2}
+                                                        defaultValue={(config.tlsSettings?.caBundles || []).join('\n')}
+                                                        onBlur={(e) => {
+                                                            const caBundles = e.target.value
+                                                                .split('\n')
+                                                                .map((entry) => entry.trim())
+                                                                .filter(Boolean);
+                                                            updateConfig({ tlsSettings: { ...config.tlsSettings, caBundles } });
+                                                        }}
+                                                        placeholder={'每行一个 PEM 文件路径：~/certs/corp-root.pem'}
+                                                        className="flex-1 resize-none rounded-lg border border-[var(--line)] bg-[var(--paper)] px-3 py-1.5 font-mono text-xs text-[var(--ink)] placeholder:text-[var(--ink-faint)] focus:border-[var(--accent)] focus:outline-none"
+                                                    />
+                                                </div>
+                                                <label className="flex items-center gap-2 text-xs text-[var(--ink-secondary)]">
+                                                    <input
+                                                        type="checkbox"
+                                                        checked={config.tlsSettings?.useSystemStore ?? true}
+                                                        onChange={(e) => updateConfig({ tlsSettings: { ...config.tlsSettings, useSystemStore: e.target.checked } })}
+                                                    />
+                                                    信任系统证书库
+                                                </label>
+                                                <label className="flex items-center gap-2 text-xs text-[var(--ink-secondary)]">
+                                                    <input
+                                                        type="checkbox"
+                                                        checked={config.tlsSettings?.insecure ?? false}
+                                                        onChange={(e) => updateConfig({ tlsSettings: { ...config.tlsSettings, insecure: e.target.checked } })}
+                                                    />
+                                                    跳过证书校验（不安全）
+                                                </label>
+                                                {config.tlsSettings?.insecure && (
+                                                    <div className="flex items-start gap-2 rounded-lg border border-[var(--error)] bg-[var(--error-bg)] px-3 py-2 text-xs text-[var(--error)]">
+                                                        <AlertCircle className="mt-0.5 h-3.5 w-3.5 shrink-0" />
+                                                        <span>已关闭 TLS 证书校验：网络中的任何人都可以读取和篡改 API 请求（包括 API Key）。仅用于临时排查问题，排查后请立即关闭。</span>
+                                                    </div>
+                                                )}
+                                                <p className="text-[10px] text-[var(--ink-faint)]">
+                                                    修改后自动生效，运行中的标签页会自动重启
+                                                </p>
+                                            </div>
+                                        </div>
                                     </div>
                                 </div>
                             )}
@@ -96,7 +96,7 @@ import {
 import { initLogger, getLoggerDiagnostics } from './logger';
 import { cleanupOldLogs } from './AgentLogger';
 import { cleanupOldUnifiedLogs, appendUnifiedLog, appendUnifiedLogBatch } from './UnifiedLogger';
-import { createSseClient, getClients } from './sse';
+import { createSseClient, getClients, getEventsSince } from './sse';
 import { checkAnthropicSubscription, getGitBranch, verifyApiKey, verifySubscription } from './provider-verify';
 
 type ImagePayload = {
@@ -118,7 +118,13 @@ type SendMessagePayload = {
   };
 };
 
-function parseArgs(argv: string[]): { agentDir: string; initialPrompt?: string; port: number } {
+function parseArgs(argv: string[]): {
+  agentDir: string;
+  initialPrompt?: string;
+  port: number;
+  portRangeEnd?: number;
+  healthNonce?: string;
+} {
   const args = argv.slice(2);
   const getArgValue = (flag: string) => {
     const index = args.indexOf(flag);
@@ -131,12 +137,23 @@ function parseArgs(argv: string[]): { agentDir: string; initialPrompt?: string;
   const agentDir = getArgValue('--agent-dir') ?? '';
   const initialPrompt = getArgValue('--prompt') ?? undefined;
   const port = Number(getArgValue('--port') ?? 3000);
+  // Optional upper bound: if --port is taken, try the following ports up to this one
+  const portRangeEnd = Number(getArgValue('--port-range-end') ?? port);
+  // Per-launch nonce from the Rust host, echoed by /health so it can verify the listener is its child
+  const healthNonce = getArgValue('--health-nonce') ?? undefined;
 
   if (!agentDir) {
     throw new Error('Missing required argument: --agent-dir <path>');
   }
 
-  return { agentDir, initialPrompt, port: Number.isNaN(port) ? 3000 : port };
+  const startPort = Number.isNaN(port) ? 3000 : port;
+  return {
+    agentDir,
+    initialPrompt,
+    port: startPort,
+    portRangeEnd: Number.isNaN(portRangeEnd) ? undefined : portRangeEnd,
+    healthNonce,
+  };
 }
 
 /**
@@ -292,6 +309,32 @@ function isPreviewableText(name: string, mimeType: string | undefined): boolean
   return TEXT_EXTENSIONS.has(extension);
 }
 
+/** Stdout line prefix parsed by src-tauri/src/sidecar.rs (SIDECAR_HANDSHAKE_PREFIX) */
+const PORT_HANDSHAKE_PREFIX = 'MYAGENTS_SIDECAR_LISTENING ';
+
+/**
+ * Bind the first free port in [startPort, endPort].
+ * Binding is atomic, so unlike probing a port and then releasing it, no other
+ * process (or concurrently starting Tab) can grab the port in between.
+ */
+function serveOnFirstFreePort<T>(startPort: number, endPort: number, serve: (port: number) => T): T {
+  let lastError: unknown = null;
+  for (let candidate = startPort; candidate <= Math.max(startPort, endPort); candidate++) {
+    try {
+      return serve(candidate);
+    } catch (error) {
+      const code = (error as { code?: string } | null)?.code;
+      const message = error instanceof Error ? error.message : String(error);
+      if (code !== 'EADDRINUSE' && !message.includes('EADDRINUSE') && !message.includes('in use')) {
+        throw error;
+      }
+      console.warn(`[server] Port ${candidate} is in use, trying next port`);
+      lastError = error;
+    }
+  }
+  throw lastError ?? new Error(`No free port in range ${startPort}-${endPort}`);
+}
+
 function jsonResponse(body: unknown, status = 200): Response {
   return new Response(JSON.stringify(body), {
     status,
@@ -357,7 +400,7 @@ interface SwitchPayload {
 }
 
 async function main() {
-  const { agentDir, initialPrompt, port } = parseArgs(process.argv);
+  const { agentDir, initialPrompt, port: preferredPort, portRangeEnd, healthNonce } = parseArgs(process.argv);
   let currentAgentDir = await ensureAgentDir(agentDir);
 
   // Initialize unified logging system (intercepts console.log and sends to SSE)
@@ -369,7 +412,7 @@ async function main() {
 
   initializeAgent(currentAgentDir, initialPrompt);
 
-  Bun.serve({
+  const server = serveOnFirstFreePort(preferredPort, portRangeEnd ?? preferredPort, (port) => Bun.serve({
     port,
     hostname: '127.0.0.1', // Explicitly bind to IPv4 for Rust proxy compatibility
     idleTimeout: 0,
@@ -377,6 +420,11 @@ async function main() {
       const url = new URL(request.url);
       const pathname = url.pathname;
 
+      // Readiness/liveness probe used by the Rust sidecar manager (polled, so not logged)
+      if (pathname === '/health' && request.method === 'GET') {
+        return jsonResponse({ status: 'ok', nonce: healthNonce ?? null, pid: process.pid }, 200);
+      }
+
       console.log(`[http] ${request.method} ${pathname}`);
 
       // Handle CORS preflight requests (for browser dev mode via Vite proxy)
@@ -403,6 +451,15 @@ async function main() {
       }
 
       if (pathname === '/chat/stream' && request.method === 'GET') {
+        // Resume: a reconnecting client only needs the broadcasts it missed
+        const missed = getEventsSince(request.headers.get('last-event-id'));
+        if (missed) {
+          const { client, response } = createSseClient(() => { }, { resumed: true });
+          console.log(`[sse] client ${client.id} resumed, replaying ${missed.length} event(s)`);
+          missed.forEach(({ id, event, data }) => client.send(event, data, id));
+          return response;
+        }
+
         const { client, response } = createSseClient(() => { });
         const state = getAgentState();
         client.send('chat:init', state);
@@ -2722,16 +2779,32 @@ async function main() {
       }
 
       // POST /api/skill/upload - Upload skill from file (.zip, .skill, .md)
+      // Accepts multipart/form-data (`file`, `scope`) or JSON with base64 `content`
       if (pathname === '/api/skill/upload' && request.method === 'POST') {
         try {
-          const payload = await request.json() as {
-            filename: string;
-            content: string; // Base64 encoded file content
-            scope: 'user' | 'project';
-          };
-
-          if (!payload.filename || !payload.content) {
-            return jsonResponse({ success: false, error: 'Filename and content are required' }, 400);
+          let payload: { filename: string; scope: 'user' | 'project' };
+          let fileBuffer: Buffer;
+
+          if ((request.headers.get('content-type') ?? '').startsWith('multipart/form-data')) {
+            const form = await request.formData();
+            const file = form.get('file');
+            if (!(file instanceof File) || !file.name) {
+              return jsonResponse({ success: false, error: 'Filename and content are required' }, 400);
+            }
+            payload = { filename: file.name, scope: form.get('scope') === 'user' ? 'user' : 'project' };
+            fileBuffer = Buffer.from(await file.arrayBuffer());
+          } else {
+            const json = await request.json() as {
+              filename: string;
+              content: string; // Base64 encoded file content
+              scope: 'user' | 'project';
+            };
+            if (!json.filename || !json.content) {
+              return jsonResponse({ success: false, error: 'Filename and content are required' }, 400);
+            }
+            payload = { filename: json.filename, scope: json.scope };
+            // Decode base64 content to buffer
+            fileBuffer = Buffer.from(json.content, 'base64');
           }
 
           const ext = extname(payload.filename).toLowerCase();
@@ -2742,9 +2815,6 @@ async function main() {
             return jsonResponse({ success: false, error: '请先设置工作目录' }, 400);
           }
 
-          // Decode base64 content to buffer
-          const fileBuffer = Buffer.from(payload.content, 'base64');
-
           // Helper: Try to extract name from SKILL.md content
           const extractNameFromContent = (content: string): string | null => {
             try {
@@ -3255,7 +3325,12 @@ async function main() {
 
       return new Response('Not Found', { status: 404 });
     }
-  });
+  }));
+  const port = server.port;
+
+  // Handshake for the Rust sidecar manager: report the port we actually bound.
+  // Written directly to stdout (not console.log) so the logger interception can't alter it.
+  process.stdout.write(`${PORT_HANDSHAKE_PREFIX}${JSON.stringify({ port, pid: process.pid })}\n`);
 
   console.log(`Web UI server listening on http://localhost:${port}`);
   console.log(`[server] Version: MCP-Install-Fix-v2`);
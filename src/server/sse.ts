@@ -2,10 +2,16 @@ import { randomUUID } from 'crypto';
 
 type SseClient = {
   id: string;
-  send: (event: string, data: unknown) => void;
+  send: (event: string, data: unknown, eventId?: string) => void;
   close: () => void;
 };
 
+type BufferedEvent = {
+  id: string;
+  event: string;
+  data: unknown;
+};
+
 const encoder = new TextEncoder();
 
 // 🔧 Fix: Use globalThis to ensure single clients Set even if module is loaded twice
@@ -19,6 +25,13 @@ const clients: Set<SseClient> =
 
 const HEARTBEAT_INTERVAL_MS = 15000;
 
+// Broadcast events carry `id: <instance>-<seq>` and the most recent ones are kept so a
+// reconnecting client (Rust SSE proxy sends Last-Event-ID) only receives what it missed.
+// The instance prefix makes ids from a previous sidecar process never match.
+const REPLAY_BUFFER_SIZE = 2000;
+const replayBuffer: BufferedEvent[] = [];
+let nextEventSeq = 1;
+
 function summarizePayload(event: string, data: unknown): string {
   if (event === 'chat:message-replay' && typeof data === 'object' && data !== null) {
     const message = (data as { message?: { id?: string } }).message;
@@ -43,8 +56,11 @@ function summarizePayload(event: string, data: unknown): string {
   }
 }
 
-function formatSse(event: string, data: unknown): Uint8Array {
+function formatSse(event: string, data: unknown, eventId?: string): Uint8Array {
   const lines: string[] = [];
+  if (eventId) {
+    lines.push(`id: ${eventId}`);
+  }
   if (event) {
     lines.push(`event: ${event}`);
   }
@@ -80,11 +96,36 @@ function heartbeatChunk(): Uint8Array {
 
 export function broadcast(event: string, data: unknown): void {
   console.log(`[sse] ${event} -> ${summarizePayload(event, data)}`);
+  const id = `${SSE_INSTANCE_ID}-${nextEventSeq++}`;
+  replayBuffer.push({ id, event, data });
+  if (replayBuffer.length > REPLAY_BUFFER_SIZE) {
+    replayBuffer.shift();
+  }
   for (const client of clients) {
-    client.send(event, data);
+    client.send(event, data, id);
   }
 }
 
+/**
+ * Broadcast events after `lastEventId`, or null when they can't be replayed
+ * (no id, id from another sidecar instance, or already evicted from the buffer)
+ */
+export function getEventsSince(lastEventId: string | null): BufferedEvent[] | null {
+  if (!lastEventId) {
+    return null;
+  }
+  const [instance, seqText] = lastEventId.split('-');
+  const seq = Number(seqText);
+  if (instance !== SSE_INSTANCE_ID || !Number.isInteger(seq) || seq >= nextEventSeq) {
+    return null;
+  }
+  const oldestSeq = nextEventSeq - replayBuffer.length;
+  if (seq < oldestSeq - 1) {
+    return null;
+  }
+  return replayBuffer.slice(seq - oldestSeq + 1);
+}
+
 /**
  * Get all active SSE clients (for logger integration)
  */
@@ -92,7 +133,7 @@ export function getClients(): SseClient[] {
   return Array.from(clients);
 }
 
-export function createSseClient(onClose: (client: SseClient) => void): {
+export function createSseClient(onClose: (client: SseClient) => void, options?: { resumed?: boolean }): {
   client: SseClient;
   response: Response;
 } {
@@ -130,9 +171,9 @@ export function createSseClient(onClose: (client: SseClient) => void): {
 
   client = {
     id: randomUUID(),
-    send: (event, data) => {
+    send: (event, data, eventId) => {
       try {
-        const payload = formatSse(event, data);
+        const payload = formatSse(event, data, eventId);
         if (!controller) {
           pending.push(payload);
           return;
@@ -170,22 +211,25 @@ export function createSseClient(onClose: (client: SseClient) => void): {
   console.log(`[sse] client connected id=${client.id} total=${clients.size}`);
 
   // Send cached log history to newly connected client (Ring Buffer for early logs)
-  try {
-    import('./logger').then(({ getLogHistory }) => {
-      const history = getLogHistory();
-      if (history.length > 0) {
-        // Small delay to ensure connection is stable
-        setTimeout(() => {
-          history.forEach(entry => {
-            client?.send('chat:log', entry);
-          });
-        }, 200);
-      }
-    }).catch(() => {
-      // Ignore if logger not yet initialized
-    });
-  } catch {
-    // Ignore
+  // Resumed clients received it on their first connection
+  if (!options?.resumed) {
+    try {
+      import('./logger').then(({ getLogHistory }) => {
+        const history = getLogHistory();
+        if (history.length > 0) {
+          // Small delay to ensure connection is stable
+          setTimeout(() => {
+            history.forEach(entry => {
+              client?.send('chat:log', entry);
+            });
+          }, 200);
+        }
+      }).catch(() => {
+        // Ignore if logger not yet initialized
+      });
+    } catch {
+      // Ignore
+    }
   }
 
   heartbeatTimer = setInterval(() => {